@@ -123,7 +123,7 @@ mod tests {
     fn test_execute_rotations() {
         let positions = exectute_dial_rotation(
             50,
-            &vec![
+            &[
                 DialRotations::Left(68),
                 DialRotations::Left(30),
                 DialRotations::Right(48),
@@ -1,4 +1,42 @@
-use std::iter;
+use std::{
+    io::{self, Write},
+    iter,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+struct ScanOptions {
+    workers: usize,
+    chunk_size: u64,
+    show_progress: bool,
+}
+
+impl ScanOptions {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = ScanOptions {
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            chunk_size: 100_000,
+            show_progress: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--workers" => options.workers = args.next().unwrap().parse().unwrap(),
+                "--chunk-size" => options.chunk_size = args.next().unwrap().parse().unwrap(),
+                "--progress" => options.show_progress = true,
+                _ => panic!("Unknown argument {arg}"),
+            }
+        }
+
+        assert!(options.workers > 0, "At least one worker is needed");
+        assert!(options.chunk_size > 0, "Chunk size has to be positive");
+
+        options
+    }
+}
 
 fn parse_input(input: &str) -> Vec<(u64, u64)> {
     input
@@ -52,23 +90,97 @@ fn find_invalid_ids_any_length((left_id, right_id): &(u64, u64)) -> Vec<u64> {
         .collect()
 }
 
+fn split_into_chunks(id_ranges: &[(u64, u64)], chunk_size: u64) -> Vec<(u64, u64)> {
+    let mut chunks = Vec::new();
+
+    // Reversed ranges contain no ids
+    for (left_id, right_id) in id_ranges
+        .iter()
+        .filter(|(left_id, right_id)| left_id <= right_id)
+    {
+        let mut chunk_start = *left_id;
+        loop {
+            let chunk_end = chunk_start.saturating_add(chunk_size - 1).min(*right_id);
+            chunks.push((chunk_start, chunk_end));
+
+            if chunk_end == *right_id {
+                break;
+            }
+            chunk_start = chunk_end + 1;
+        }
+    }
+
+    chunks
+}
+
+fn print_progress(scanned_ids: u64, total_ids: u64) {
+    let percentage = scanned_ids as f64 / total_ids as f64 * 100.0;
+    eprint!("\rScanned {scanned_ids}/{total_ids} ids ({percentage:.1}%)");
+    io::stderr().flush().unwrap();
+}
+
+fn find_invalid_ids_parallel(
+    id_ranges: &[(u64, u64)],
+    find_invalid_ids: fn(&(u64, u64)) -> Vec<u64>,
+    options: &ScanOptions,
+) -> Vec<u64> {
+    let chunks = split_into_chunks(id_ranges, options.chunk_size);
+    let total_ids = chunks
+        .iter()
+        .map(|(left_id, right_id)| right_id - left_id + 1)
+        .sum::<u64>();
+
+    // Every chunk gets its own result slot, so the output order does not depend on scheduling
+    let results = chunks
+        .iter()
+        .map(|_| Mutex::new(Vec::new()))
+        .collect::<Vec<_>>();
+    let next_chunk = AtomicUsize::new(0);
+    let scanned_ids = AtomicU64::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..options.workers.min(chunks.len()) {
+            scope.spawn(|| {
+                loop {
+                    let chunk_index = next_chunk.fetch_add(1, Ordering::Relaxed);
+                    let Some(chunk) = chunks.get(chunk_index) else {
+                        break;
+                    };
+
+                    *results[chunk_index].lock().unwrap() = find_invalid_ids(chunk);
+
+                    let chunk_ids = chunk.1 - chunk.0 + 1;
+                    let scanned = scanned_ids.fetch_add(chunk_ids, Ordering::Relaxed) + chunk_ids;
+                    if options.show_progress {
+                        print_progress(scanned, total_ids);
+                    }
+                }
+            });
+        }
+    });
+
+    if options.show_progress {
+        eprintln!();
+    }
+
+    results
+        .into_iter()
+        .flat_map(|result| result.into_inner().unwrap())
+        .collect()
+}
+
 fn main() {
     let input = include_str!("../inputs/data_day_2.txt");
     let id_ranges = parse_input(input);
+    let options = ScanOptions::from_args(std::env::args().skip(1));
 
     // Solution for puzzle 1
-    let invalid_ids = id_ranges
-        .iter()
-        .flat_map(find_invalid_ids_halves)
-        .collect::<Vec<u64>>();
+    let invalid_ids = find_invalid_ids_parallel(&id_ranges, find_invalid_ids_halves, &options);
     let invalid_ids_sum = invalid_ids.iter().sum::<u64>();
     println!("The sum of invalid ids with two repeating halves is {invalid_ids_sum}");
 
     // Solution for puzzle 2
-    let invalid_ids = id_ranges
-        .iter()
-        .flat_map(find_invalid_ids_any_length)
-        .collect::<Vec<u64>>();
+    let invalid_ids = find_invalid_ids_parallel(&id_ranges, find_invalid_ids_any_length, &options);
     let invalid_ids_sum = invalid_ids.iter().sum::<u64>();
     println!("The sum of invalid ids with repeating sequences is {invalid_ids_sum}");
 }
@@ -100,10 +212,10 @@ mod tests {
 
     #[test]
     fn test_is_invalid_id_halves() {
-        assert_eq!(is_invalid_id_halves(&998), false);
-        assert_eq!(is_invalid_id_halves(&1010), true);
-        assert_eq!(is_invalid_id_halves(&1012), false);
-        assert_eq!(is_invalid_id_halves(&101010), false);
+        assert!(!is_invalid_id_halves(&998));
+        assert!(is_invalid_id_halves(&1010));
+        assert!(!is_invalid_id_halves(&1012));
+        assert!(!is_invalid_id_halves(&101010));
     }
 
     #[test]
@@ -129,10 +241,10 @@ mod tests {
 
     #[test]
     fn test_is_invalid_id_any_length() {
-        assert_eq!(is_invalid_id_any_length(&998), false);
-        assert_eq!(is_invalid_id_any_length(&1010), true);
-        assert_eq!(is_invalid_id_any_length(&1012), false);
-        assert_eq!(is_invalid_id_any_length(&101010), true);
+        assert!(!is_invalid_id_any_length(&998));
+        assert!(is_invalid_id_any_length(&1010));
+        assert!(!is_invalid_id_any_length(&1012));
+        assert!(is_invalid_id_any_length(&101010));
     }
 
     #[test]
@@ -161,4 +273,83 @@ mod tests {
             vec![2121212121]
         );
     }
+
+    #[test]
+    fn test_split_into_chunks() {
+        assert_eq!(split_into_chunks(&[(11, 22)], 100), vec![(11, 22)]);
+        assert_eq!(
+            split_into_chunks(&[(11, 22), (95, 115)], 5),
+            vec![
+                (11, 15),
+                (16, 20),
+                (21, 22),
+                (95, 99),
+                (100, 104),
+                (105, 109),
+                (110, 114),
+                (115, 115)
+            ]
+        );
+        assert_eq!(
+            split_into_chunks(&[(u64::MAX - 2, u64::MAX)], 2),
+            vec![(u64::MAX - 2, u64::MAX - 1), (u64::MAX, u64::MAX)]
+        );
+        assert_eq!(
+            split_into_chunks(&[(30, 20), (5, 6), (7, 7)], 10),
+            vec![(5, 6), (7, 7)]
+        );
+    }
+
+    #[test]
+    fn test_find_invalid_ids_parallel_with_reversed_range() {
+        let options = ScanOptions {
+            workers: 2,
+            chunk_size: 5,
+            show_progress: false,
+        };
+
+        assert_eq!(
+            find_invalid_ids_parallel(&[(30, 20), (11, 22)], find_invalid_ids_halves, &options),
+            vec![11, 22]
+        );
+        assert_eq!(
+            find_invalid_ids_parallel(&[(30, 20)], find_invalid_ids_halves, &options),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_find_invalid_ids_parallel() {
+        let id_ranges = parse_input(
+            "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124",
+        );
+        let sequential_ids = id_ranges
+            .iter()
+            .flat_map(find_invalid_ids_any_length)
+            .collect::<Vec<u64>>();
+
+        for (workers, chunk_size) in [(1, 1), (3, 2), (8, 7), (16, 1000)] {
+            let options = ScanOptions {
+                workers,
+                chunk_size,
+                show_progress: false,
+            };
+            assert_eq!(
+                find_invalid_ids_parallel(&id_ranges, find_invalid_ids_any_length, &options),
+                sequential_ids
+            );
+        }
+    }
+
+    #[test]
+    fn test_scan_options_from_args() {
+        let options = ScanOptions::from_args(
+            ["--workers", "3", "--chunk-size", "42", "--progress"]
+                .into_iter()
+                .map(String::from),
+        );
+        assert_eq!(options.workers, 3);
+        assert_eq!(options.chunk_size, 42);
+        assert!(options.show_progress);
+    }
 }
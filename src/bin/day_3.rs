@@ -1,78 +1,804 @@
-fn parse_input(input: &str) -> Vec<Vec<u8>> {
+use std::{
+    fmt::{self, Display},
+    iter::Sum,
+    mem,
+    ops::Add,
+    process,
+};
+
+const MAX_RADIX: usize = 16;
+
+// Unsigned decimal number of arbitrary size, stored with the least significant digit first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Joltage {
+    digits: Vec<u8>,
+}
+
+impl Joltage {
+    fn from_digits(digits: impl DoubleEndedIterator<Item = u8>) -> Self {
+        let mut digits = digits.rev().collect::<Vec<u8>>();
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        Joltage { digits }
+    }
+
+    fn from_radix_digits(digits: impl DoubleEndedIterator<Item = u8>, radix: u32) -> Self {
+        if radix == 10 {
+            return Joltage::from_digits(digits);
+        }
+
+        digits.fold(Joltage::default(), |acc, digit| acc.mul_add(radix, digit))
+    }
+
+    fn mul_add(self, factor: u32, addend: u8) -> Joltage {
+        let mut digits = Vec::with_capacity(self.digits.len() + 2);
+        let mut carry = addend as u32;
+
+        for digit in self.digits {
+            let product = digit as u32 * factor + carry;
+            digits.push((product % 10) as u8);
+            carry = product / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        Joltage { digits }
+    }
+}
+
+// Only tests build joltages from plain numbers
+#[cfg(test)]
+impl From<u64> for Joltage {
+    fn from(value: u64) -> Self {
+        Joltage::from_digits(value.to_string().bytes().map(|digit| digit - b'0'))
+    }
+}
+
+impl Add<&Joltage> for Joltage {
+    type Output = Joltage;
+
+    fn add(self, other: &Joltage) -> Joltage {
+        let mut digits = Vec::with_capacity(self.digits.len().max(other.digits.len()) + 1);
+        let mut carry = 0;
+
+        for index in 0..self.digits.len().max(other.digits.len()) {
+            let sum = self.digits.get(index).unwrap_or(&0)
+                + other.digits.get(index).unwrap_or(&0)
+                + carry;
+            digits.push(sum % 10);
+            carry = sum / 10;
+        }
+        if carry > 0 {
+            digits.push(carry);
+        }
+
+        Joltage { digits }
+    }
+}
+
+impl Sum for Joltage {
+    fn sum<I: Iterator<Item = Joltage>>(iter: I) -> Self {
+        iter.fold(Joltage::default(), |acc, joltage| acc + &joltage)
+    }
+}
+
+impl Display for Joltage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.digits.is_empty() {
+            write!(f, "0")
+        } else {
+            self.digits
+                .iter()
+                .rev()
+                .try_for_each(|digit| write!(f, "{digit}"))
+        }
+    }
+}
+
+const FAULTY_BATTERY: u8 = u8::MAX;
+
+#[derive(Debug, PartialEq)]
+enum BankError {
+    InvalidBattery { column: usize, battery: char },
+    NotEnoughBatteries { needed: usize, selectable: usize },
+    TargetUnreachable,
+}
+
+impl Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BankError::InvalidBattery { column, battery } => {
+                write!(f, "invalid battery '{battery}' at column {column}")
+            }
+            BankError::NotEnoughBatteries { needed, selectable } => write!(
+                f,
+                "{needed} batteries needed, but only {selectable} can be selected"
+            ),
+            BankError::TargetUnreachable => write!(f, "target joltage is unreachable"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum JoltageObjective {
+    Maximum,
+    Minimum,
+    AtLeast(Vec<u8>),
+}
+
+impl JoltageObjective {
+    fn select_batteries(
+        &self,
+        needed_batteries: usize,
+        minimum_gap: usize,
+        bank_joltages: &[u8],
+    ) -> Result<Vec<(usize, u8)>, BankError> {
+        let is_constrained = minimum_gap > 0 || bank_joltages.contains(&FAULTY_BATTERY);
+        if !is_constrained && needed_batteries <= bank_joltages.len() {
+            match self {
+                JoltageObjective::Maximum => {
+                    return Ok(find_maximum_joltage_batteries(
+                        needed_batteries,
+                        bank_joltages,
+                    ));
+                }
+                JoltageObjective::Minimum => {
+                    return Ok(find_minimum_joltage_batteries(
+                        needed_batteries,
+                        bank_joltages,
+                    ));
+                }
+                JoltageObjective::AtLeast(_) => {}
+            }
+        }
+
+        self.select_constrained_batteries(
+            needed_batteries,
+            &ConstrainedBank::new(bank_joltages, minimum_gap),
+        )
+    }
+
+    fn select_constrained_batteries(
+        &self,
+        needed_batteries: usize,
+        bank: &ConstrainedBank,
+    ) -> Result<Vec<(usize, u8)>, BankError> {
+        if needed_batteries > bank.capacities[0] {
+            return Err(BankError::NotEnoughBatteries {
+                needed: needed_batteries,
+                selectable: bank.capacities[0],
+            });
+        }
+
+        match self {
+            JoltageObjective::Maximum => Ok(bank.select_extreme(needed_batteries, 0, true)),
+            JoltageObjective::Minimum => Ok(bank.select_extreme(needed_batteries, 0, false)),
+            JoltageObjective::AtLeast(target_joltage) => bank
+                .select_at_least(needed_batteries, target_joltage)
+                .ok_or(BankError::TargetUnreachable),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            JoltageObjective::Maximum => String::from("output joltage"),
+            JoltageObjective::Minimum => String::from("minimum output joltage"),
+            JoltageObjective::AtLeast(target_joltage) => format!(
+                "output joltage of at least {}",
+                target_joltage
+                    .iter()
+                    .map(|&digit| render_joltage(digit))
+                    .collect::<String>()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SweepFormat {
+    Table,
+    Csv,
+}
+
+#[derive(Debug, PartialEq)]
+enum BankPolicy {
+    Skip,
+    Fail,
+}
+
+struct JoltageOptions {
+    objective: JoltageObjective,
+    minimum_gap: usize,
+    radix: u32,
+    bank_policy: BankPolicy,
+    show_selection: bool,
+    sweep: Option<SweepFormat>,
+}
+
+impl JoltageOptions {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = JoltageOptions {
+            objective: JoltageObjective::Maximum,
+            minimum_gap: 0,
+            radix: 10,
+            bank_policy: BankPolicy::Skip,
+            show_selection: false,
+            sweep: None,
+        };
+        let mut target_joltage = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--minimum" => options.objective = JoltageObjective::Minimum,
+                "--at-least" => target_joltage = args.next(),
+                "--minimum-gap" => options.minimum_gap = args.next().unwrap().parse().unwrap(),
+                "--hex" => options.radix = 16,
+                "--bad-banks" => {
+                    options.bank_policy = match args.next().as_deref() {
+                        Some("skip") => BankPolicy::Skip,
+                        Some("fail") => BankPolicy::Fail,
+                        policy => panic!("Unknown bad bank policy {policy:?}"),
+                    }
+                }
+                "--show-selection" => options.show_selection = true,
+                "--sweep" => {
+                    options.sweep = match args.next().as_deref() {
+                        Some("table") => Some(SweepFormat::Table),
+                        Some("csv") => Some(SweepFormat::Csv),
+                        format => panic!("Unknown sweep format {format:?}"),
+                    }
+                }
+                _ => panic!("Unknown argument {arg}"),
+            }
+        }
+
+        if let Some(target_joltage) = target_joltage {
+            options.objective =
+                JoltageObjective::AtLeast(parse_joltage(&target_joltage, options.radix));
+        }
+
+        options
+    }
+}
+
+fn parse_joltage(joltage: &str, radix: u32) -> Vec<u8> {
+    joltage
+        .trim()
+        .chars()
+        .map(|digit| digit.to_digit(radix).unwrap() as u8)
+        .collect()
+}
+
+fn parse_bank(line: &str, radix: u32) -> Result<Vec<u8>, BankError> {
+    line.trim()
+        .chars()
+        .enumerate()
+        .map(|(index, battery)| match battery {
+            'x' => Ok(FAULTY_BATTERY),
+            digit => digit.to_digit(radix).map(|joltage| joltage as u8).ok_or(
+                BankError::InvalidBattery {
+                    column: index + 1,
+                    battery,
+                },
+            ),
+        })
+        .collect()
+}
+
+fn parse_input(input: &str, radix: u32) -> Vec<Result<Vec<u8>, BankError>> {
     input
         .trim()
         .lines()
-        .map(|line| {
-            line.trim()
-                .chars()
-                .map(|digit| digit.to_digit(10).unwrap() as u8)
-                .collect()
-        })
+        .map(|line| parse_bank(line, radix))
         .collect()
 }
 
-fn find_first_max(bank_joltages: &[u8]) -> (usize, &u8) {
+fn find_extreme_joltage_batteries(
+    needed_batteries: usize,
+    bank_joltages: &[u8],
+    is_better: fn(u8, u8) -> bool,
+) -> Vec<(usize, u8)> {
+    let mut removable_batteries = bank_joltages.len().saturating_sub(needed_batteries);
+    let mut selected_batteries: Vec<(usize, u8)> = Vec::with_capacity(bank_joltages.len());
+
+    for (index, &joltage) in bank_joltages.iter().enumerate() {
+        while removable_batteries > 0
+            && selected_batteries
+                .last()
+                .is_some_and(|&(_, last_joltage)| is_better(joltage, last_joltage))
+        {
+            selected_batteries.pop();
+            removable_batteries -= 1;
+        }
+        selected_batteries.push((index, joltage));
+    }
+
+    selected_batteries.truncate(needed_batteries);
+    selected_batteries
+}
+
+fn find_removal_order(bank_joltages: &[u8], is_better: fn(u8, u8) -> bool) -> Vec<usize> {
+    let mut removal_order = Vec::with_capacity(bank_joltages.len());
+    let mut kept_batteries: Vec<usize> = Vec::with_capacity(bank_joltages.len());
+
+    // Same pops as the greedy stack without a removal budget, so the first n - k removals
+    // leave exactly the selection for k batteries
+    for (index, &joltage) in bank_joltages.iter().enumerate() {
+        while let Some(&last_index) = kept_batteries.last()
+            && is_better(joltage, bank_joltages[last_index])
+        {
+            removal_order.push(last_index);
+            kept_batteries.pop();
+        }
+        kept_batteries.push(index);
+    }
+    removal_order.extend(kept_batteries.into_iter().rev());
+
+    removal_order
+}
+
+fn find_maximum_joltage_batteries(
+    needed_batteries: usize,
+    bank_joltages: &[u8],
+) -> Vec<(usize, u8)> {
+    find_extreme_joltage_batteries(needed_batteries, bank_joltages, |joltage, last_joltage| {
+        joltage > last_joltage
+    })
+}
+
+fn find_minimum_joltage_batteries(
+    needed_batteries: usize,
+    bank_joltages: &[u8],
+) -> Vec<(usize, u8)> {
+    find_extreme_joltage_batteries(needed_batteries, bank_joltages, |joltage, last_joltage| {
+        joltage < last_joltage
+    })
+}
+
+// Selection of batteries that skips faulty ones and keeps a minimum gap between chosen ones
+struct ConstrainedBank<'a> {
+    joltages: &'a [u8],
+    minimum_gap: usize,
+    // capacities[i] is the most batteries that can be selected from index i onwards
+    capacities: Vec<usize>,
+    // next_batteries[i][d] is the first selectable index >= i holding joltage d
+    next_batteries: Vec<[usize; MAX_RADIX]>,
+}
+
+impl<'a> ConstrainedBank<'a> {
+    fn new(joltages: &'a [u8], minimum_gap: usize) -> Self {
+        let mut capacities = vec![0; joltages.len() + minimum_gap + 2];
+        let mut next_batteries = vec![[joltages.len(); MAX_RADIX]; joltages.len() + 1];
+
+        for index in (0..joltages.len()).rev() {
+            capacities[index] = capacities[index + 1];
+            next_batteries[index] = next_batteries[index + 1];
+
+            if joltages[index] != FAULTY_BATTERY {
+                capacities[index] = capacities[index].max(1 + capacities[index + minimum_gap + 1]);
+                next_batteries[index][joltages[index] as usize] = index;
+            }
+        }
+
+        ConstrainedBank {
+            joltages,
+            minimum_gap,
+            capacities,
+            next_batteries,
+        }
+    }
+
+    fn find_feasible_battery(
+        &self,
+        start_index: usize,
+        joltage: u8,
+        remaining_batteries: usize,
+    ) -> Option<usize> {
+        let index = self.next_batteries[start_index.min(self.joltages.len())][joltage as usize];
+
+        (index < self.joltages.len()
+            && 1 + self.capacities[index + self.minimum_gap + 1] >= remaining_batteries)
+            .then_some(index)
+    }
+
+    fn select_extreme(
+        &self,
+        needed_batteries: usize,
+        mut start_index: usize,
+        maximize: bool,
+    ) -> Vec<(usize, u8)> {
+        let mut selected_batteries = Vec::with_capacity(needed_batteries);
+
+        for remaining_batteries in (1..=needed_batteries).rev() {
+            let (index, joltage) = (0..MAX_RADIX as u8)
+                .map(|rank| {
+                    if maximize {
+                        MAX_RADIX as u8 - 1 - rank
+                    } else {
+                        rank
+                    }
+                })
+                .find_map(|joltage| {
+                    self.find_feasible_battery(start_index, joltage, remaining_batteries)
+                        .map(|index| (index, joltage))
+                })
+                .unwrap();
+
+            selected_batteries.push((index, joltage));
+            start_index = index + self.minimum_gap + 1;
+        }
+
+        selected_batteries
+    }
+
+    fn select_at_least(
+        &self,
+        needed_batteries: usize,
+        target_joltage: &[u8],
+    ) -> Option<Vec<(usize, u8)>> {
+        let target_joltage = &target_joltage[target_joltage
+            .iter()
+            .position(|&digit| digit != 0)
+            .unwrap_or(target_joltage.len())..];
+        if target_joltage.len() > needed_batteries {
+            return None;
+        }
+
+        // Pad the target to the selection length, so comparing values becomes comparing digit strings
+        let mut target = vec![0; needed_batteries - target_joltage.len()];
+        target.extend_from_slice(target_joltage);
+
+        // Match the longest possible prefix of the target as early as possible in the bank
+        let mut matched_indices: Vec<usize> = Vec::with_capacity(needed_batteries);
+        for (position, &digit) in target.iter().enumerate() {
+            let start_index = matched_indices
+                .last()
+                .map_or(0, |index| index + self.minimum_gap + 1);
+            match self.find_feasible_battery(start_index, digit, needed_batteries - position) {
+                Some(index) => matched_indices.push(index),
+                None => break,
+            }
+        }
+
+        if matched_indices.len() == needed_batteries {
+            return Some(matched_indices.into_iter().zip(target).collect());
+        }
+
+        // Otherwise exceed the target right after the longest prefix that still allows it
+        for prefix_length in (0..=matched_indices.len()).rev() {
+            let start_index = matched_indices[..prefix_length]
+                .last()
+                .map_or(0, |index| index + self.minimum_gap + 1);
+            let remaining_batteries = needed_batteries - prefix_length;
+
+            let exceeding_battery =
+                (target[prefix_length] + 1..MAX_RADIX as u8).find_map(|joltage| {
+                    self.find_feasible_battery(start_index, joltage, remaining_batteries)
+                        .map(|index| (index, joltage))
+                });
+
+            if let Some((index, joltage)) = exceeding_battery {
+                let mut selected_batteries = matched_indices[..prefix_length]
+                    .iter()
+                    .map(|&index| (index, self.joltages[index]))
+                    .collect::<Vec<(usize, u8)>>();
+                selected_batteries.push((index, joltage));
+                selected_batteries.extend(self.select_extreme(
+                    remaining_batteries - 1,
+                    index + self.minimum_gap + 1,
+                    false,
+                ));
+
+                return Some(selected_batteries);
+            }
+        }
+
+        None
+    }
+}
+
+fn render_joltage(joltage: u8) -> char {
+    if joltage == FAULTY_BATTERY {
+        'x'
+    } else {
+        char::from_digit(joltage as u32, MAX_RADIX as u32).unwrap()
+    }
+}
+
+fn render_bank_selection(bank_joltages: &[u8], selected_batteries: &[(usize, u8)]) -> String {
+    let mut selected_batteries = selected_batteries.iter().peekable();
+
     bank_joltages
         .iter()
         .enumerate()
-        .reduce(|(current_max_index, current_max), (index, value)| {
-            if value > current_max {
-                (index, value)
+        .map(|(index, joltage)| {
+            if selected_batteries
+                .next_if(|(selected_index, _)| *selected_index == index)
+                .is_some()
+            {
+                format!("\x1b[1;32m{}\x1b[0m", render_joltage(*joltage))
             } else {
-                (current_max_index, current_max)
+                render_joltage(*joltage).to_string()
             }
         })
-        .unwrap()
+        .collect()
 }
 
-fn find_maximum_joltage(needed_batteries: usize, bank_joltages: &[u8]) -> Vec<u8> {
-    if bank_joltages.len() == 1 {
-        Vec::from(bank_joltages)
-    } else {
-        let (max_joltage_index, max_joltage) =
-            // find_first_max(&bank_joltages[0..=(bank_joltages.len() - needed_batteries)]);
-            find_first_max(&bank_joltages[0..=(bank_joltages.len() - needed_batteries)]);
-        let mut max_joltages = vec![*max_joltage];
+fn print_bank_selections(
+    needed_batteries: usize,
+    objective: &JoltageObjective,
+    minimum_gap: usize,
+    joltages: &[Vec<u8>],
+) {
+    for bank_joltages in joltages {
+        match objective.select_batteries(needed_batteries, minimum_gap, bank_joltages) {
+            Ok(selected_batteries) => {
+                let indices = selected_batteries
+                    .iter()
+                    .map(|(index, _)| index.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
+                println!(
+                    "{} (batteries {indices})",
+                    render_bank_selection(bank_joltages, &selected_batteries)
+                );
+            }
+            Err(error) => println!("{} ({error})", render_bank_selection(bank_joltages, &[])),
+        }
+    }
+}
+
+fn get_total_output_joltage(
+    needed_batteries: usize,
+    objective: &JoltageObjective,
+    minimum_gap: usize,
+    radix: u32,
+    joltages: &[Vec<u8>],
+) -> (Joltage, Vec<(usize, BankError)>) {
+    let mut total_output_joltage = Joltage::default();
+    let mut bank_errors = Vec::new();
 
-        if needed_batteries > 1 {
-            max_joltages.append(&mut find_maximum_joltage(
-                needed_batteries - 1,
-                &bank_joltages[max_joltage_index + 1..],
-            ));
+    for (bank_index, bank_joltages) in joltages.iter().enumerate() {
+        match objective.select_batteries(needed_batteries, minimum_gap, bank_joltages) {
+            Ok(selected_batteries) => {
+                total_output_joltage = total_output_joltage
+                    + &Joltage::from_radix_digits(
+                        selected_batteries.into_iter().map(|(_, joltage)| joltage),
+                        radix,
+                    );
+            }
+            Err(error) => bank_errors.push((bank_index, error)),
         }
+    }
+
+    (total_output_joltage, bank_errors)
+}
 
-        max_joltages
+fn sweep_total_output_joltages(
+    objective: &JoltageObjective,
+    minimum_gap: usize,
+    radix: u32,
+    joltages: &[Vec<u8>],
+) -> Vec<(Joltage, Vec<(usize, BankError)>)> {
+    let max_batteries = joltages.iter().map(Vec::len).max().unwrap_or(0);
+    let mut total_output_joltages = vec![Joltage::default(); max_batteries];
+    let mut bank_errors = (0..max_batteries).map(|_| Vec::new()).collect::<Vec<_>>();
+
+    for (bank_index, bank_joltages) in joltages.iter().enumerate() {
+        let is_better: Option<fn(u8, u8) -> bool> = match objective {
+            JoltageObjective::Maximum => Some(|joltage, last_joltage| joltage > last_joltage),
+            JoltageObjective::Minimum => Some(|joltage, last_joltage| joltage < last_joltage),
+            JoltageObjective::AtLeast(_) => None,
+        };
+
+        match is_better {
+            Some(is_better) if minimum_gap == 0 && !bank_joltages.contains(&FAULTY_BATTERY) => {
+                let mut is_kept = vec![true; bank_joltages.len()];
+                for (removed_batteries, removed_index) in
+                    find_removal_order(bank_joltages, is_better)
+                        .into_iter()
+                        .enumerate()
+                {
+                    let total =
+                        &mut total_output_joltages[bank_joltages.len() - removed_batteries - 1];
+                    *total = mem::take(total)
+                        + &Joltage::from_radix_digits(
+                            bank_joltages
+                                .iter()
+                                .zip(&is_kept)
+                                .filter(|(_, kept)| **kept)
+                                .map(|(joltage, _)| *joltage),
+                            radix,
+                        );
+                    is_kept[removed_index] = false;
+                }
+            }
+            _ => {
+                // The lookup tables only depend on the bank, so they are shared by every k
+                let bank = ConstrainedBank::new(bank_joltages, minimum_gap);
+                for needed_batteries in 1..=bank_joltages.len() {
+                    match objective.select_constrained_batteries(needed_batteries, &bank) {
+                        Ok(selected_batteries) => {
+                            let total = &mut total_output_joltages[needed_batteries - 1];
+                            *total = mem::take(total)
+                                + &Joltage::from_radix_digits(
+                                    selected_batteries.into_iter().map(|(_, joltage)| joltage),
+                                    radix,
+                                );
+                        }
+                        Err(error) => bank_errors[needed_batteries - 1].push((bank_index, error)),
+                    }
+                }
+            }
+        }
     }
+
+    total_output_joltages.into_iter().zip(bank_errors).collect()
 }
 
-fn get_total_output_joltage(needed_batteries: usize, joltages: &[Vec<u8>]) -> u64 {
-    joltages
+fn render_sweep(
+    format: &SweepFormat,
+    description: &str,
+    total_output_joltages: &[Joltage],
+) -> String {
+    let rows = total_output_joltages
         .iter()
-        .map(|bank_joltages| {
-            find_maximum_joltage(needed_batteries, bank_joltages)
-                .iter()
-                .fold(0, |acc, &joltage| acc * 10 + joltage as u64)
-        })
-        .sum()
+        .enumerate()
+        .map(|(index, total)| (index + 1, total));
+
+    match format {
+        SweepFormat::Table => {
+            let mut table = format!("Batteries | Total {description}\n");
+            for (needed_batteries, total) in rows {
+                table.push_str(&format!("{needed_batteries:>9} | {total}\n"));
+            }
+            table
+        }
+        SweepFormat::Csv => {
+            let mut csv = String::from("batteries,total_joltage\n");
+            for (needed_batteries, total) in rows {
+                csv.push_str(&format!("{needed_batteries},{total}\n"));
+            }
+            csv
+        }
+    }
+}
+
+fn handle_bank_errors(bank_errors: &[(usize, BankError)], policy: &BankPolicy) {
+    for (line_index, error) in bank_errors {
+        match policy {
+            BankPolicy::Skip => eprintln!("Skipped bank {}: {error}", line_index + 1),
+            BankPolicy::Fail => {
+                eprintln!("Bad bank {}: {error}", line_index + 1);
+                process::exit(1);
+            }
+        }
+    }
 }
 
 fn main() {
     let input = include_str!("../inputs/data_day_3.txt");
-    let joltages = parse_input(input);
+    let options = JoltageOptions::from_args(std::env::args().skip(1));
+    let description = options.objective.describe();
+
+    let mut joltages = Vec::new();
+    let mut bank_lines = Vec::new();
+    let mut parse_errors = Vec::new();
+    for (line_index, bank) in parse_input(input, options.radix).into_iter().enumerate() {
+        match bank {
+            Ok(bank_joltages) => {
+                joltages.push(bank_joltages);
+                bank_lines.push(line_index);
+            }
+            Err(error) => parse_errors.push((line_index, error)),
+        }
+    }
+    handle_bank_errors(&parse_errors, &options.bank_policy);
+    let to_line_errors = |bank_errors: Vec<(usize, BankError)>| {
+        bank_errors
+            .into_iter()
+            .map(|(bank_index, error)| (bank_lines[bank_index], error))
+            .collect::<Vec<(usize, BankError)>>()
+    };
+
+    if let Some(format) = &options.sweep {
+        let mut total_output_joltages = Vec::new();
+        for (total_output_joltage, bank_errors) in sweep_total_output_joltages(
+            &options.objective,
+            options.minimum_gap,
+            options.radix,
+            &joltages,
+        ) {
+            handle_bank_errors(&to_line_errors(bank_errors), &options.bank_policy);
+            total_output_joltages.push(total_output_joltage);
+        }
+        print!(
+            "{}",
+            render_sweep(format, &description, &total_output_joltages)
+        );
+        return;
+    }
 
     // Solution for puzzle 1
-    let total_output_joltage = get_total_output_joltage(2, &joltages);
-    println!("The total output joltage is {total_output_joltage}");
+    if options.show_selection {
+        print_bank_selections(2, &options.objective, options.minimum_gap, &joltages);
+    }
+    let (total_output_joltage, bank_errors) = get_total_output_joltage(
+        2,
+        &options.objective,
+        options.minimum_gap,
+        options.radix,
+        &joltages,
+    );
+    handle_bank_errors(&to_line_errors(bank_errors), &options.bank_policy);
+    println!("The total {description} is {total_output_joltage}");
 
     // Solution for puzzle 2
-    let total_output_joltage = get_total_output_joltage(12, &joltages);
-    println!("The total output joltage with static friction is {total_output_joltage}");
+    if options.show_selection {
+        print_bank_selections(12, &options.objective, options.minimum_gap, &joltages);
+    }
+    let (total_output_joltage, bank_errors) = get_total_output_joltage(
+        12,
+        &options.objective,
+        options.minimum_gap,
+        options.radix,
+        &joltages,
+    );
+    handle_bank_errors(&to_line_errors(bank_errors), &options.bank_policy);
+    println!("The total {description} with static friction is {total_output_joltage}");
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn find_maximum_joltage(needed_batteries: usize, bank_joltages: &[u8]) -> Vec<u8> {
+        find_maximum_joltage_batteries(needed_batteries, bank_joltages)
+            .into_iter()
+            .map(|(_, joltage)| joltage)
+            .collect()
+    }
+
+    fn find_first_max(bank_joltages: &[u8]) -> (usize, &u8) {
+        bank_joltages
+            .iter()
+            .enumerate()
+            .reduce(|(current_max_index, current_max), (index, value)| {
+                if value > current_max {
+                    (index, value)
+                } else {
+                    (current_max_index, current_max)
+                }
+            })
+            .unwrap()
+    }
+
+    fn find_maximum_joltage_recursive(needed_batteries: usize, bank_joltages: &[u8]) -> Vec<u8> {
+        if bank_joltages.len() == 1 {
+            Vec::from(bank_joltages)
+        } else {
+            let (max_joltage_index, max_joltage) =
+                find_first_max(&bank_joltages[0..=(bank_joltages.len() - needed_batteries)]);
+            let mut max_joltages = vec![*max_joltage];
+
+            if needed_batteries > 1 {
+                max_joltages.append(&mut find_maximum_joltage_recursive(
+                    needed_batteries - 1,
+                    &bank_joltages[max_joltage_index + 1..],
+                ));
+            }
+
+            max_joltages
+        }
+    }
+
     #[test]
     fn test_parse_input() {
         let input = "
@@ -82,21 +808,60 @@ mod tests {
             818181911112111
         ";
         assert_eq!(
-            parse_input(input),
+            parse_input(input, 10),
+            vec![
+                Ok(vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1]),
+                Ok(vec![8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9]),
+                Ok(vec![2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8]),
+                Ok(vec![8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_input_with_bad_banks() {
+        let input = "
+            9876
+            81a9
+            8x1f
+        ";
+        assert_eq!(
+            parse_input(input, 10),
+            vec![
+                Ok(vec![9, 8, 7, 6]),
+                Err(BankError::InvalidBattery {
+                    column: 3,
+                    battery: 'a'
+                }),
+                Err(BankError::InvalidBattery {
+                    column: 4,
+                    battery: 'f'
+                })
+            ]
+        );
+        assert_eq!(
+            parse_input(input, 16),
             vec![
-                vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1],
-                vec![8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9],
-                vec![2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8],
-                vec![8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1]
+                Ok(vec![9, 8, 7, 6]),
+                Ok(vec![8, 1, 10, 9]),
+                Ok(vec![8, FAULTY_BATTERY, 1, 15])
             ]
         );
     }
 
+    #[test]
+    fn test_parse_bank() {
+        assert_eq!(
+            parse_bank("81x19", 10).unwrap(),
+            vec![8, 1, FAULTY_BATTERY, 1, 9]
+        );
+    }
+
     #[test]
     fn test_find_first_max() {
-        assert_eq!(find_first_max(&vec![1]), (0, &1));
-        assert_eq!(find_first_max(&vec![1, 2, 3]), (2, &3));
-        assert_eq!(find_first_max(&vec![1, 2, 3, 3]), (2, &3));
+        assert_eq!(find_first_max(&[1]), (0, &1));
+        assert_eq!(find_first_max(&[1, 2, 3]), (2, &3));
+        assert_eq!(find_first_max(&[1, 2, 3, 3]), (2, &3));
     }
 
     #[test]
@@ -119,6 +884,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_maximum_joltage_batteries() {
+        assert_eq!(
+            find_maximum_joltage_batteries(2, &[8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9]),
+            vec![(0, 8), (14, 9)]
+        );
+        assert_eq!(
+            find_maximum_joltage_batteries(2, &[8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1]),
+            vec![(6, 9), (11, 2)]
+        );
+        assert_eq!(
+            find_maximum_joltage_batteries(4, &[2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8]),
+            vec![(2, 4), (5, 4), (13, 7), (14, 8)]
+        );
+    }
+
+    #[test]
+    fn test_render_bank_selection() {
+        assert_eq!(
+            render_bank_selection(&parse_bank("81x19", 10).unwrap(), &[(4, 9)]),
+            "81x1\x1b[1;32m9\x1b[0m"
+        );
+        assert_eq!(
+            render_bank_selection(&[8, 1, 8, 1, 9, 1], &[(4, 9), (5, 1)]),
+            "8181\x1b[1;32m9\x1b[0m\x1b[1;32m1\x1b[0m"
+        );
+        assert_eq!(render_bank_selection(&[1, 2, 3], &[]), "123");
+    }
+
     #[test]
     fn test_find_maximum_joltage_with_static_friction() {
         assert_eq!(
@@ -144,14 +938,17 @@ mod tests {
         assert_eq!(
             get_total_output_joltage(
                 2,
-                &vec![
+                &JoltageObjective::Maximum,
+                0,
+                10,
+                &[
                     vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1],
                     vec![8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9],
                     vec![2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8],
                     vec![8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1]
                 ]
             ),
-            357
+            (Joltage::from(357), vec![])
         );
     }
 
@@ -160,14 +957,392 @@ mod tests {
         assert_eq!(
             get_total_output_joltage(
                 12,
-                &vec![
+                &JoltageObjective::Maximum,
+                0,
+                10,
+                &[
                     vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1],
                     vec![8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9],
                     vec![2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8],
                     vec![8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1]
                 ]
             ),
-            3121910778619
+            (Joltage::from(3121910778619), vec![])
+        );
+    }
+
+    fn next_random(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    #[test]
+    fn test_find_maximum_joltage_matches_recursive() {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+
+        for _ in 0..1000 {
+            let bank_length = 1 + (next_random(&mut seed) % 40) as usize;
+            let bank_joltages = (0..bank_length)
+                .map(|_| 1 + (next_random(&mut seed) % 9) as u8)
+                .collect::<Vec<u8>>();
+
+            for needed_batteries in 1..=bank_length {
+                assert_eq!(
+                    find_maximum_joltage(needed_batteries, &bank_joltages),
+                    find_maximum_joltage_recursive(needed_batteries, &bank_joltages),
+                    "bank {bank_joltages:?} with {needed_batteries} batteries"
+                );
+            }
+        }
+    }
+
+    fn find_all_selections(
+        needed_batteries: usize,
+        minimum_gap: usize,
+        bank_joltages: &[u8],
+    ) -> Vec<Vec<u8>> {
+        if needed_batteries == 0 {
+            return vec![vec![]];
+        }
+
+        (0..bank_joltages.len())
+            .filter(|index| bank_joltages[*index] != FAULTY_BATTERY)
+            .flat_map(|index| {
+                let remaining_joltages = bank_joltages
+                    .get(index + minimum_gap + 1..)
+                    .unwrap_or_default();
+                find_all_selections(needed_batteries - 1, minimum_gap, remaining_joltages)
+                    .into_iter()
+                    .map(move |mut selection| {
+                        selection.insert(0, bank_joltages[index]);
+                        selection
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_minimum_joltage_batteries() {
+        assert_eq!(
+            find_minimum_joltage_batteries(2, &[9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1]),
+            vec![(8, 1), (9, 1)]
+        );
+        assert_eq!(
+            find_minimum_joltage_batteries(2, &[8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9]),
+            vec![(1, 1), (2, 1)]
+        );
+        assert_eq!(
+            find_minimum_joltage_batteries(12, &[2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8]),
+            vec![
+                (0, 2),
+                (3, 2),
+                (4, 3),
+                (6, 2),
+                (7, 3),
+                (8, 4),
+                (9, 2),
+                (10, 3),
+                (11, 4),
+                (12, 2),
+                (13, 7),
+                (14, 8)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_at_least() {
+        let bank_joltages = [9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1];
+        let bank = ConstrainedBank::new(&bank_joltages, 0);
+        assert_eq!(bank.select_at_least(2, &[5, 0]), Some(vec![(4, 5), (8, 1)]));
+        assert_eq!(bank.select_at_least(2, &[9, 8]), Some(vec![(0, 9), (1, 8)]));
+        assert_eq!(bank.select_at_least(2, &[9, 9]), None);
+        assert_eq!(bank.select_at_least(2, &[1, 0, 0]), None);
+        assert_eq!(bank.select_at_least(2, &[7]), Some(vec![(8, 1), (9, 1)]));
+
+        let bank_joltages = [8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1];
+        let bank = ConstrainedBank::new(&bank_joltages, 0);
+        assert_eq!(
+            bank.select_at_least(3, &[8, 8, 5]),
+            Some(vec![(0, 8), (2, 8), (4, 8)])
+        );
+        assert_eq!(
+            bank.select_at_least(3, &[8, 9, 5]),
+            Some(vec![(6, 9), (7, 1), (8, 1)])
+        );
+
+        let bank = ConstrainedBank::new(&bank_joltages, 1);
+        assert_eq!(
+            bank.select_at_least(3, &[8, 8, 9]),
+            Some(vec![(0, 8), (2, 8), (6, 9)])
+        );
+    }
+
+    #[test]
+    fn test_select_batteries_with_constraints() {
+        let bank_joltages = parse_bank("81x1819x11x2111", 10).unwrap();
+
+        assert_eq!(
+            JoltageObjective::Maximum.select_batteries(3, 1, &bank_joltages),
+            Ok(vec![(6, 9), (11, 2), (13, 1)])
+        );
+        assert_eq!(
+            JoltageObjective::Minimum.select_batteries(3, 2, &bank_joltages),
+            Ok(vec![(1, 1), (5, 1), (8, 1)])
+        );
+        assert_eq!(
+            JoltageObjective::Maximum.select_batteries(7, 1, &bank_joltages),
+            Err(BankError::NotEnoughBatteries {
+                needed: 7,
+                selectable: 6
+            })
+        );
+        assert_eq!(
+            JoltageObjective::Maximum.select_batteries(
+                16,
+                0,
+                &parse_bank("987654321111111", 10).unwrap()
+            ),
+            Err(BankError::NotEnoughBatteries {
+                needed: 16,
+                selectable: 15
+            })
+        );
+    }
+
+    #[test]
+    fn test_select_batteries_matches_brute_force() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for _ in 0..300 {
+            let bank_length = 1 + (next_random(&mut seed) % 10) as usize;
+            let minimum_gap = (next_random(&mut seed) % 3) as usize;
+            let bank_joltages = (0..bank_length)
+                .map(|_| match next_random(&mut seed) % 12 {
+                    0 => FAULTY_BATTERY,
+                    joltage => 1 + (joltage % 9) as u8,
+                })
+                .collect::<Vec<u8>>();
+
+            for needed_batteries in 1..=bank_length {
+                let selections = find_all_selections(needed_batteries, minimum_gap, &bank_joltages);
+                let target_joltage = (0..needed_batteries)
+                    .map(|_| (next_random(&mut seed) % 10) as u8)
+                    .collect::<Vec<u8>>();
+
+                let digits = |objective: &JoltageObjective| {
+                    objective
+                        .select_batteries(needed_batteries, minimum_gap, &bank_joltages)
+                        .ok()
+                        .map(|batteries| batteries.into_iter().map(|(_, joltage)| joltage))
+                        .map(Vec::from_iter)
+                };
+
+                assert_eq!(
+                    digits(&JoltageObjective::Maximum),
+                    selections.iter().max().cloned()
+                );
+                assert_eq!(
+                    digits(&JoltageObjective::Minimum),
+                    selections.iter().min().cloned()
+                );
+                assert_eq!(
+                    digits(&JoltageObjective::AtLeast(target_joltage.clone())),
+                    selections
+                        .iter()
+                        .filter(|selection| **selection >= target_joltage)
+                        .min()
+                        .cloned(),
+                    "bank {bank_joltages:?} with target {target_joltage:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_total_output_joltage_with_objectives() {
+        let joltages = [
+            vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1],
+            vec![8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9],
+            vec![2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8],
+            vec![8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1],
+        ];
+
+        assert_eq!(
+            get_total_output_joltage(2, &JoltageObjective::Minimum, 0, 10, &joltages),
+            (Joltage::from(11 + 11 + 22 + 11), vec![])
+        );
+        assert_eq!(
+            get_total_output_joltage(2, &JoltageObjective::AtLeast(vec![8, 5]), 0, 10, &joltages),
+            (
+                Joltage::from(85 + 89 + 88),
+                vec![(2, BankError::TargetUnreachable)]
+            )
+        );
+    }
+
+    #[test]
+    fn test_joltage() {
+        assert_eq!(Joltage::from(0).to_string(), "0");
+        assert_eq!(
+            Joltage::from_radix_digits([15, 0, 15].into_iter(), 16),
+            Joltage::from(0xf0f)
+        );
+        assert_eq!(
+            Joltage::from_digits([0, 0, 4, 2].into_iter()).to_string(),
+            "42"
+        );
+        assert_eq!(
+            (Joltage::from(u64::MAX) + &Joltage::from(u64::MAX)).to_string(),
+            "36893488147419103230"
+        );
+        assert_eq!(
+            [Joltage::from(999), Joltage::from(1), Joltage::from(0)]
+                .into_iter()
+                .sum::<Joltage>(),
+            Joltage::from(1000)
+        );
+    }
+
+    #[test]
+    fn test_get_total_output_joltage_with_short_and_hex_banks() {
+        let joltages = [vec![9, 8, 7], vec![8], vec![10, 15, 1]];
+
+        assert_eq!(
+            get_total_output_joltage(2, &JoltageObjective::Maximum, 0, 16, &joltages),
+            (
+                Joltage::from(0x98 + 0xf1),
+                vec![(
+                    1,
+                    BankError::NotEnoughBatteries {
+                        needed: 2,
+                        selectable: 1
+                    }
+                )]
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_total_output_joltage_without_overflow() {
+        let joltages = vec![vec![9; 40]; 3];
+
+        assert_eq!(
+            get_total_output_joltage(30, &JoltageObjective::Maximum, 0, 10, &joltages)
+                .0
+                .to_string(),
+            format!("2{}7", "9".repeat(29))
+        );
+    }
+
+    #[test]
+    fn test_find_removal_order() {
+        assert_eq!(
+            find_removal_order(&[8, 1, 8, 1, 9, 1], |joltage, last_joltage| {
+                joltage > last_joltage
+            }),
+            vec![1, 3, 2, 0, 5, 4]
+        );
+
+        let bank_joltages = [2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8];
+        let removal_order = find_removal_order(&bank_joltages, |joltage, last_joltage| {
+            joltage > last_joltage
+        });
+        for needed_batteries in 1..=bank_joltages.len() {
+            let removed = &removal_order[..bank_joltages.len() - needed_batteries];
+            assert_eq!(
+                (0..bank_joltages.len())
+                    .filter(|index| !removed.contains(index))
+                    .map(|index| bank_joltages[index])
+                    .collect::<Vec<u8>>(),
+                find_maximum_joltage(needed_batteries, &bank_joltages)
+            );
+        }
+    }
+
+    #[test]
+    fn test_sweep_total_output_joltages() {
+        let joltages = [
+            vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1],
+            vec![8, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 9],
+            vec![2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 7, 8],
+            vec![8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1],
+            vec![7, FAULTY_BATTERY, 3, 9, FAULTY_BATTERY, 1, 6, 2],
+        ];
+
+        for (objective, minimum_gap) in [0, 1].into_iter().flat_map(|minimum_gap| {
+            [
+                JoltageObjective::Maximum,
+                JoltageObjective::Minimum,
+                JoltageObjective::AtLeast(vec![8, 5]),
+            ]
+            .map(|objective| (objective, minimum_gap))
+        }) {
+            let total_output_joltages =
+                sweep_total_output_joltages(&objective, minimum_gap, 10, &joltages);
+            assert_eq!(total_output_joltages.len(), 15);
+
+            for (index, (total, bank_errors)) in total_output_joltages.iter().enumerate() {
+                let long_enough_banks = joltages
+                    .iter()
+                    .filter(|bank_joltages| bank_joltages.len() > index)
+                    .cloned()
+                    .collect::<Vec<Vec<u8>>>();
+                assert_eq!(
+                    *total,
+                    get_total_output_joltage(
+                        index + 1,
+                        &objective,
+                        minimum_gap,
+                        10,
+                        &long_enough_banks
+                    )
+                    .0,
+                    "{objective:?} with {} batteries and a gap of {minimum_gap}",
+                    index + 1
+                );
+                // Banks too short for this many batteries are left out of the sweep
+                assert_eq!(
+                    *bank_errors,
+                    get_total_output_joltage(index + 1, &objective, minimum_gap, 10, &joltages)
+                        .1
+                        .into_iter()
+                        .filter(|(bank_index, _)| joltages[*bank_index].len() > index)
+                        .collect::<Vec<(usize, BankError)>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_sweep() {
+        let total_output_joltages = [Joltage::from(36), Joltage::from(357)];
+
+        assert_eq!(
+            render_sweep(
+                &SweepFormat::Table,
+                "output joltage",
+                &total_output_joltages
+            ),
+            "Batteries | Total output joltage\n        1 | 36\n        2 | 357\n"
+        );
+        assert_eq!(
+            render_sweep(&SweepFormat::Csv, "output joltage", &total_output_joltages),
+            "batteries,total_joltage\n1,36\n2,357\n"
+        );
+    }
+
+    #[test]
+    fn test_joltage_options_from_args() {
+        let options = JoltageOptions::from_args(
+            ["--at-least", "1f", "--hex", "--bad-banks", "fail"]
+                .into_iter()
+                .map(String::from),
         );
+        assert_eq!(options.objective, JoltageObjective::AtLeast(vec![1, 15]));
+        assert_eq!(options.radix, 16);
+        assert_eq!(options.bank_policy, BankPolicy::Fail);
     }
 }
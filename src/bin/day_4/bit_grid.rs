@@ -0,0 +1,255 @@
+use crate::{AccessRule, RollMap, Topology};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+const COUNTER_PLANES: usize = 5;
+
+// Bit-sliced neighbour counter for 64 cells at once, plane i holds bit i of every count
+#[derive(Clone, Copy, Default)]
+struct NeighborCounter {
+    planes: [u64; COUNTER_PLANES],
+}
+
+impl NeighborCounter {
+    fn add(&mut self, neighbors: u64) {
+        let mut carry = neighbors;
+        for plane in self.planes.iter_mut() {
+            let next_carry = *plane & carry;
+            *plane ^= carry;
+            carry = next_carry;
+        }
+    }
+
+    fn less_than(&self, threshold: usize) -> u64 {
+        if threshold >= 1 << COUNTER_PLANES {
+            return u64::MAX;
+        }
+
+        let mut less = 0;
+        let mut equal = u64::MAX;
+
+        for (bit, plane) in self.planes.iter().enumerate().rev() {
+            if threshold >> bit & 1 == 1 {
+                less |= equal & !plane;
+                equal &= plane;
+            } else {
+                equal &= !plane;
+            }
+        }
+
+        less
+    }
+}
+
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn from_map(map: &[Vec<bool>]) -> Self {
+        let width = map.iter().map(Vec::len).max().unwrap_or(0);
+        let words_per_row = width.div_ceil(WORD_BITS);
+        let mut grid = BitGrid {
+            width,
+            height: map.len(),
+            words_per_row,
+            words: vec![0; words_per_row * map.len()],
+        };
+
+        for (y, row) in map.iter().enumerate() {
+            for (x, is_roll) in row.iter().enumerate() {
+                if *is_roll {
+                    grid.words[y * words_per_row + x / WORD_BITS] |= 1 << (x % WORD_BITS);
+                }
+            }
+        }
+
+        grid
+    }
+
+    fn word(&self, y: i64, word_index: i64) -> u64 {
+        if y < 0 || y >= self.height as i64 || word_index < 0 {
+            return 0;
+        }
+
+        self.words[y as usize * self.words_per_row..][..self.words_per_row]
+            .get(word_index as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn cell(&self, x: i64, y: i64, topology: Topology) -> bool {
+        let (x, y) = match topology {
+            Topology::Torus if self.width > 0 && self.height > 0 => (
+                x.rem_euclid(self.width as i64),
+                y.rem_euclid(self.height as i64),
+            ),
+            _ => (x, y),
+        };
+
+        if x < 0 || x >= self.width as i64 || y < 0 || y >= self.height as i64 {
+            topology == Topology::Walls
+        } else {
+            self.word(y, x / WORD_BITS as i64) >> (x as usize % WORD_BITS) & 1 == 1
+        }
+    }
+
+    // Bit i of the result holds the cell at x + dx of the cell at bit i of the word
+    fn shifted_word(&self, y: i64, word_index: i64, dx: i32, topology: Topology) -> u64 {
+        let start = word_index * WORD_BITS as i64 + dx as i64;
+        let is_inside = y >= 0
+            && y < self.height as i64
+            && start >= 0
+            && start + WORD_BITS as i64 <= self.width as i64;
+
+        // Only words reaching over the map edges have to be assembled cell by cell
+        if topology != Topology::Open && !is_inside {
+            return (0..WORD_BITS)
+                .filter(|bit| self.cell(start + *bit as i64, y, topology))
+                .fold(0, |word, bit| word | 1 << bit);
+        }
+
+        let word = self.word(y, word_index);
+        let shift = dx.unsigned_abs() as usize;
+
+        match dx {
+            0 => word,
+            dx if dx < 0 => word << shift | self.word(y, word_index - 1) >> (WORD_BITS - shift),
+            _ => word >> shift | self.word(y, word_index + 1) << (WORD_BITS - shift),
+        }
+    }
+
+    fn find_accessible_word(&self, y: usize, word_index: usize, rule: &AccessRule) -> u64 {
+        let rolls = self.words[y * self.words_per_row + word_index];
+        if rolls == 0 {
+            return 0;
+        }
+
+        let mut counter = NeighborCounter::default();
+        for (dx, dy) in rule.neighborhood.offsets() {
+            counter.add(self.shifted_word(
+                y as i64 + *dy as i64,
+                word_index as i64,
+                *dx,
+                rule.topology,
+            ));
+        }
+
+        rolls & counter.less_than(rule.threshold)
+    }
+
+    fn find_accessible_words(&self, rule: &AccessRule) -> Vec<u64> {
+        (0..self.height)
+            .flat_map(|y| (0..self.words_per_row).map(move |word_index| (y, word_index)))
+            .map(|(y, word_index)| self.find_accessible_word(y, word_index, rule))
+            .collect()
+    }
+
+    fn coordinates(&self, accessible_words: &[u64]) -> Vec<(usize, usize)> {
+        let mut coordinates = Vec::new();
+
+        for (index, word) in accessible_words.iter().enumerate() {
+            let mut word = *word;
+            while word != 0 {
+                let bit = word.trailing_zeros() as usize;
+                coordinates.push((
+                    (index % self.words_per_row) * WORD_BITS + bit,
+                    index / self.words_per_row,
+                ));
+                word &= word - 1;
+            }
+        }
+
+        coordinates
+    }
+}
+
+impl RollMap for BitGrid {
+    fn find_accessible_rolls(&self, rule: &AccessRule) -> Option<Vec<(usize, usize)>> {
+        let coordinates = self.coordinates(&self.find_accessible_words(rule));
+
+        if coordinates.is_empty() {
+            None
+        } else {
+            Some(coordinates)
+        }
+    }
+
+    fn clear_up_map(&mut self, rule: &AccessRule) -> Vec<Vec<(usize, usize)>> {
+        let mut removed_rolls = Vec::new();
+
+        loop {
+            let accessible_words = self.find_accessible_words(rule);
+            if accessible_words.iter().all(|word| *word == 0) {
+                break;
+            }
+
+            for (word, accessible_word) in self.words.iter_mut().zip(&accessible_words) {
+                *word &= !accessible_word;
+            }
+            removed_rolls.push(self.coordinates(&accessible_words));
+        }
+
+        removed_rolls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{generate_access_rules, generate_random_map};
+
+    #[test]
+    fn test_neighbor_counter() {
+        let mut counter = NeighborCounter::default();
+        for neighbors in [0b1111, 0b1110, 0b1100, 0b1000, 0b1000] {
+            counter.add(neighbors);
+        }
+
+        assert_eq!(counter.planes[0], 0b1101);
+        assert_eq!(counter.planes[1], 0b0110);
+        assert_eq!(counter.planes[2], 0b1000);
+        assert_eq!(counter.less_than(3) & 0b1111, 0b0011);
+        assert_eq!(counter.less_than(4) & 0b1111, 0b0111);
+        assert_eq!(counter.less_than(32), u64::MAX);
+    }
+
+    #[test]
+    fn test_from_map() {
+        let map = vec![
+            vec![false, false, true, true],
+            vec![true, true, true, false],
+        ];
+        let grid = BitGrid::from_map(&map);
+
+        assert_eq!(grid.width, 4);
+        assert_eq!(grid.height, 2);
+        assert_eq!(grid.words_per_row, 1);
+        assert_eq!(grid.words, vec![0b1100, 0b0111]);
+    }
+
+    #[test]
+    fn test_matches_dense_map() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for (width, height) in [(1, 1), (4, 2), (63, 3), (64, 5), (65, 7), (130, 20)] {
+            for rule in generate_access_rules() {
+                let mut map = generate_random_map(&mut seed, width, height);
+                let mut grid = BitGrid::from_map(&map);
+
+                assert_eq!(
+                    grid.find_accessible_rolls(&rule),
+                    crate::find_accessible_rolls(&map, &rule)
+                );
+                assert_eq!(
+                    grid.clear_up_map(&rule),
+                    crate::clear_up_map(&mut map, &rule)
+                );
+                assert_eq!(grid.words, BitGrid::from_map(&map).words);
+            }
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::VecDeque;
+
+use crate::{AccessRule, RollMap, Topology, VON_NEUMANN_OFFSETS, resolve_cell};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Empty,
+    Roll,
+    FixedRoll,
+    Wall,
+    Dock,
+}
+
+impl Cell {
+    fn from_symbol(symbol: char) -> Self {
+        match symbol {
+            '@' => Cell::Roll,
+            'F' => Cell::FixedRoll,
+            '#' => Cell::Wall,
+            'D' => Cell::Dock,
+            _ => Cell::Empty,
+        }
+    }
+
+    fn is_occupied(&self) -> bool {
+        matches!(self, Cell::Roll | Cell::FixedRoll | Cell::Wall)
+    }
+}
+
+pub struct CellMap {
+    cells: Vec<Vec<Cell>>,
+}
+
+impl CellMap {
+    pub fn parse(input: &str) -> Self {
+        CellMap {
+            cells: input
+                .trim()
+                .lines()
+                .map(|line| line.trim().chars().map(Cell::from_symbol).collect())
+                .collect(),
+        }
+    }
+
+    // Fixed rolls are never removed, so they are rendered like the stable core
+    pub fn rolls(&self) -> Vec<Vec<bool>> {
+        self.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| matches!(cell, Cell::Roll | Cell::FixedRoll))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn count_occupied_neighbors(&self, x: usize, y: usize, rule: &AccessRule) -> usize {
+        rule.neighborhood
+            .offsets()
+            .iter()
+            .filter(|(dx, dy)| {
+                match resolve_cell(x as i32 + dx, y as i32 + dy, &self.cells, rule.topology) {
+                    Some((x, y)) => self.cells[y][x].is_occupied(),
+                    None => rule.topology == Topology::Walls,
+                }
+            })
+            .count()
+    }
+
+    // Forklifts drive from the docks across free cells and pick up rolls from any free cell next
+    // to them, without docks on the map every roll counts as reachable
+    fn find_reachable_cells(&self, topology: Topology) -> Option<Vec<Vec<bool>>> {
+        let mut is_reached = self
+            .cells
+            .iter()
+            .map(|row| vec![false; row.len()])
+            .collect::<Vec<Vec<bool>>>();
+        let mut docks = Vec::new();
+
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if *cell == Cell::Dock {
+                    is_reached[y][x] = true;
+                    docks.push((x, y));
+                }
+            }
+        }
+        if docks.is_empty() {
+            return None;
+        }
+
+        self.extend_reachable_cells(docks, &mut is_reached, topology);
+        Some(is_reached)
+    }
+
+    // Floods outwards from already reached cells and returns every cell reached on the way
+    fn extend_reachable_cells(
+        &self,
+        reached_cells: Vec<(usize, usize)>,
+        is_reached: &mut [Vec<bool>],
+        topology: Topology,
+    ) -> Vec<(usize, usize)> {
+        let mut newly_reached_cells = Vec::new();
+        let mut queue = VecDeque::from(reached_cells);
+
+        while let Some((x, y)) = queue.pop_front() {
+            for (dx, dy) in VON_NEUMANN_OFFSETS {
+                if let Some((next_x, next_y)) =
+                    resolve_cell(x as i32 + dx, y as i32 + dy, &self.cells, topology)
+                    && !self.cells[next_y][next_x].is_occupied()
+                    && !is_reached[next_y][next_x]
+                {
+                    is_reached[next_y][next_x] = true;
+                    newly_reached_cells.push((next_x, next_y));
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+
+        newly_reached_cells
+    }
+
+    fn is_reachable(
+        &self,
+        x: usize,
+        y: usize,
+        reachable_cells: &Option<Vec<Vec<bool>>>,
+        topology: Topology,
+    ) -> bool {
+        let Some(is_reached) = reachable_cells else {
+            return true;
+        };
+
+        VON_NEUMANN_OFFSETS.iter().any(|(dx, dy)| {
+            resolve_cell(x as i32 + dx, y as i32 + dy, &self.cells, topology)
+                .is_some_and(|(x, y)| is_reached[y][x])
+        })
+    }
+}
+
+impl RollMap for CellMap {
+    fn find_accessible_rolls(&self, rule: &AccessRule) -> Option<Vec<(usize, usize)>> {
+        let reachable_cells = self.find_reachable_cells(rule.topology);
+        let mut coordinates = Vec::new();
+
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if *cell == Cell::Roll
+                    && self.count_occupied_neighbors(x, y, rule) < rule.threshold
+                    && self.is_reachable(x, y, &reachable_cells, rule.topology)
+                {
+                    coordinates.push((x, y));
+                }
+            }
+        }
+
+        if coordinates.is_empty() {
+            None
+        } else {
+            Some(coordinates)
+        }
+    }
+
+    // Removing a roll only lowers the counts of its neighbours and can only open up new paths
+    // starting at its own cell, so after the first scan just the cells around those changes
+    // are checked again
+    fn clear_up_map(&mut self, rule: &AccessRule) -> Vec<Vec<(usize, usize)>> {
+        let mut neighbor_rolls = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                (0..row.len())
+                    .map(|x| self.count_occupied_neighbors(x, y, rule))
+                    .collect::<Vec<usize>>()
+            })
+            .collect::<Vec<Vec<usize>>>();
+        let mut reachable_cells = self.find_reachable_cells(rule.topology);
+        let mut is_queued = self
+            .cells
+            .iter()
+            .map(|row| vec![false; row.len()])
+            .collect::<Vec<Vec<bool>>>();
+
+        let mut removed_rolls = Vec::new();
+        let mut accessible_rolls = self.find_accessible_rolls(rule).unwrap_or_default();
+
+        while !accessible_rolls.is_empty() {
+            for (x, y) in &accessible_rolls {
+                self.cells[*y][*x] = Cell::Empty;
+            }
+
+            let mut candidates = Vec::new();
+            for (x, y) in &accessible_rolls {
+                for (dx, dy) in rule.neighborhood.offsets() {
+                    if let Some((neighbor_x, neighbor_y)) =
+                        resolve_cell(*x as i32 + dx, *y as i32 + dy, &self.cells, rule.topology)
+                    {
+                        neighbor_rolls[neighbor_y][neighbor_x] -= 1;
+                        candidates.push((neighbor_x, neighbor_y));
+                    }
+                }
+            }
+            // Every removed roll was next to a reached cell, so its own cell is reached now
+            if let Some(is_reached) = &mut reachable_cells {
+                for (x, y) in &accessible_rolls {
+                    is_reached[*y][*x] = true;
+                }
+                let newly_reached_cells = self.extend_reachable_cells(
+                    accessible_rolls.clone(),
+                    is_reached,
+                    rule.topology,
+                );
+                for (x, y) in accessible_rolls.iter().chain(&newly_reached_cells) {
+                    candidates.extend(VON_NEUMANN_OFFSETS.iter().filter_map(|(dx, dy)| {
+                        resolve_cell(*x as i32 + dx, *y as i32 + dy, &self.cells, rule.topology)
+                    }));
+                }
+            }
+
+            let mut next_accessible_rolls = Vec::new();
+            for (x, y) in candidates {
+                if self.cells[y][x] == Cell::Roll
+                    && !is_queued[y][x]
+                    && neighbor_rolls[y][x] < rule.threshold
+                    && self.is_reachable(x, y, &reachable_cells, rule.topology)
+                {
+                    is_queued[y][x] = true;
+                    next_accessible_rolls.push((x, y));
+                }
+            }
+            next_accessible_rolls.sort_unstable_by_key(|(x, y)| (*y, *x));
+
+            removed_rolls.push(accessible_rolls);
+            accessible_rolls = next_accessible_rolls;
+        }
+
+        removed_rolls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{generate_access_rules, generate_random_map};
+
+    fn clear_up_map_rescanning(map: &mut CellMap, rule: &AccessRule) -> Vec<Vec<(usize, usize)>> {
+        let mut removed_rolls = Vec::new();
+
+        while let Some(accessible_rolls) = map.find_accessible_rolls(rule) {
+            for (x, y) in &accessible_rolls {
+                map.cells[*y][*x] = Cell::Empty;
+            }
+
+            removed_rolls.push(accessible_rolls);
+        }
+
+        removed_rolls
+    }
+
+    // Free cells of a second random map are turned into walls, fixed rolls and docks
+    fn generate_random_cell_map(seed: &mut u64, width: usize, height: usize) -> CellMap {
+        let rolls = generate_random_map(seed, width, height);
+        let special_cells = generate_random_map(seed, width, height);
+
+        CellMap {
+            cells: rolls
+                .iter()
+                .zip(&special_cells)
+                .enumerate()
+                .map(|(y, (row, special_row))| {
+                    row.iter()
+                        .zip(special_row)
+                        .enumerate()
+                        .map(|(x, cell)| match cell {
+                            (true, _) => Cell::Roll,
+                            (false, false) => Cell::Empty,
+                            (false, true) => [Cell::Wall, Cell::Empty, Cell::FixedRoll, Cell::Dock]
+                                [(x * 7 + y * 3) % 4],
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let map = CellMap::parse(
+            "
+            D.@
+            #F.
+        ",
+        );
+
+        assert_eq!(
+            map.cells,
+            vec![
+                vec![Cell::Dock, Cell::Empty, Cell::Roll],
+                vec![Cell::Wall, Cell::FixedRoll, Cell::Empty],
+            ]
+        );
+        assert_eq!(
+            map.rolls(),
+            vec![vec![false, false, true], vec![false, true, false]]
+        );
+    }
+
+    #[test]
+    fn test_clear_up_map() {
+        let mut map = CellMap::parse(
+            "
+            D.@@
+            ##@.
+            @@.F
+        ",
+        );
+
+        assert_eq!(
+            map.count_occupied_neighbors(2, 1, &AccessRule::default()),
+            5
+        );
+        assert_eq!(
+            map.clear_up_map(&AccessRule::default()),
+            vec![
+                vec![(2, 0)],
+                vec![(3, 0)],
+                vec![(2, 1)],
+                vec![(1, 2)],
+                vec![(0, 2)]
+            ]
+        );
+        assert_eq!(map.cells[2][3], Cell::FixedRoll);
+
+        let map = CellMap::parse(
+            "
+            ..@@
+            ##@.
+            @@.F
+        ",
+        );
+        assert_eq!(
+            map.find_accessible_rolls(&AccessRule::default()),
+            Some(vec![(2, 0), (3, 0), (0, 2)])
+        );
+    }
+
+    #[test]
+    fn test_matches_dense_map_without_docks() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for rule in generate_access_rules() {
+            let mut map = generate_random_map(&mut seed, 13, 9);
+            let mut cell_map = CellMap {
+                cells: map
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|is_roll| if *is_roll { Cell::Roll } else { Cell::Empty })
+                            .collect()
+                    })
+                    .collect(),
+            };
+
+            assert_eq!(
+                cell_map.clear_up_map(&rule),
+                crate::clear_up_map(&mut map, &rule)
+            );
+        }
+    }
+
+    #[test]
+    fn test_clear_up_map_matches_rescanning() {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+
+        for (width, height) in [(1, 1), (4, 3), (13, 9), (32, 32)] {
+            for rule in generate_access_rules() {
+                let mut map = generate_random_cell_map(&mut seed, width, height);
+                let mut rescanned_map = CellMap {
+                    cells: map.cells.clone(),
+                };
+
+                assert_eq!(
+                    map.clear_up_map(&rule),
+                    clear_up_map_rescanning(&mut rescanned_map, &rule)
+                );
+                assert_eq!(map.cells, rescanned_map.cells);
+            }
+        }
+    }
+}
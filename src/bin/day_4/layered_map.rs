@@ -0,0 +1,330 @@
+use crate::{AccessRule, Topology, parse_input};
+
+// Every roll touches the 26 cells of the surrounding 3×3×3 cube
+fn get_offsets() -> impl Iterator<Item = (i32, i32, i32)> {
+    (-1..=1)
+        .flat_map(|dz| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (dx, dy, dz))))
+        .filter(|offset| *offset != (0, 0, 0))
+}
+
+pub struct LayeredMap {
+    layers: Vec<Vec<Vec<bool>>>,
+}
+
+impl LayeredMap {
+    // Layers are separated by blank lines, starting with the ground floor
+    pub fn parse(input: &str) -> Self {
+        let mut layers = vec![String::new()];
+        for line in input.trim().lines() {
+            if line.trim().is_empty() {
+                if !layers.last().unwrap().is_empty() {
+                    layers.push(String::new());
+                }
+            } else {
+                let layer = layers.last_mut().unwrap();
+                layer.push_str(line);
+                layer.push('\n');
+            }
+        }
+
+        LayeredMap {
+            layers: layers.iter().map(|layer| parse_input(layer)).collect(),
+        }
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    fn resolve_cell(
+        &self,
+        x: i32,
+        y: i32,
+        z: i32,
+        topology: Topology,
+    ) -> Option<(usize, usize, usize)> {
+        let (x, y, z) = match topology {
+            Topology::Torus if !self.layers.is_empty() => {
+                let z = z.rem_euclid(self.layers.len() as i32);
+                let layer = &self.layers[z as usize];
+                if layer.is_empty() {
+                    return None;
+                }
+                let y = y.rem_euclid(layer.len() as i32);
+                let row_length = layer[y as usize].len() as i32;
+                if row_length == 0 {
+                    return None;
+                }
+                (x.rem_euclid(row_length), y, z)
+            }
+            _ => (x, y, z),
+        };
+
+        if z < 0
+            || z >= self.layers.len() as i32
+            || y < 0
+            || y >= self.layers[z as usize].len() as i32
+            || x < 0
+            || x >= self.layers[z as usize][y as usize].len() as i32
+        {
+            None
+        } else {
+            Some((x as usize, y as usize, z as usize))
+        }
+    }
+
+    fn count_neighbor_rolls(&self, x: usize, y: usize, z: usize, topology: Topology) -> usize {
+        get_offsets()
+            .filter(|(dx, dy, dz)| {
+                match self.resolve_cell(x as i32 + dx, y as i32 + dy, z as i32 + dz, topology) {
+                    Some((x, y, z)) => self.layers[z][y][x],
+                    None => topology == Topology::Walls,
+                }
+            })
+            .count()
+    }
+
+    fn rolls(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.layers.iter().enumerate().flat_map(|(z, layer)| {
+            layer.iter().enumerate().flat_map(move |(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, is_roll)| **is_roll)
+                    .map(move |(x, _)| (x, y, z))
+            })
+        })
+    }
+
+    // Only the Moore neighbourhood is defined in three dimensions, so the rule's neighbourhood
+    // is ignored
+    pub fn find_accessible_rolls(&self, rule: &AccessRule) -> Option<Vec<(usize, usize, usize)>> {
+        let coordinates = self
+            .rolls()
+            .filter(|(x, y, z)| {
+                self.count_neighbor_rolls(*x, *y, *z, rule.topology) < rule.threshold
+            })
+            .collect::<Vec<(usize, usize, usize)>>();
+
+        if coordinates.is_empty() {
+            None
+        } else {
+            Some(coordinates)
+        }
+    }
+
+    pub fn clear_up_map(&mut self, rule: &AccessRule) -> Vec<Vec<(usize, usize, usize)>> {
+        let (threshold, topology) = (rule.threshold, rule.topology);
+        let mut neighbor_rolls = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(z, layer)| {
+                layer
+                    .iter()
+                    .enumerate()
+                    .map(|(y, row)| {
+                        (0..row.len())
+                            .map(|x| self.count_neighbor_rolls(x, y, z, topology))
+                            .collect::<Vec<usize>>()
+                    })
+                    .collect::<Vec<Vec<usize>>>()
+            })
+            .collect::<Vec<Vec<Vec<usize>>>>();
+        let mut is_queued = self
+            .layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|row| vec![false; row.len()])
+                    .collect::<Vec<Vec<bool>>>()
+            })
+            .collect::<Vec<Vec<Vec<bool>>>>();
+
+        let mut removed_rolls = Vec::new();
+        let mut accessible_rolls = self.find_accessible_rolls(rule).unwrap_or_default();
+
+        while !accessible_rolls.is_empty() {
+            for (x, y, z) in &accessible_rolls {
+                self.layers[*z][*y][*x] = false;
+            }
+
+            let mut next_accessible_rolls = Vec::new();
+            for (x, y, z) in &accessible_rolls {
+                for (dx, dy, dz) in get_offsets() {
+                    if let Some((neighbor_x, neighbor_y, neighbor_z)) =
+                        self.resolve_cell(*x as i32 + dx, *y as i32 + dy, *z as i32 + dz, topology)
+                        && self.layers[neighbor_z][neighbor_y][neighbor_x]
+                    {
+                        let count = &mut neighbor_rolls[neighbor_z][neighbor_y][neighbor_x];
+                        *count -= 1;
+
+                        let is_queued = &mut is_queued[neighbor_z][neighbor_y][neighbor_x];
+                        if *count < threshold && !*is_queued {
+                            *is_queued = true;
+                            next_accessible_rolls.push((neighbor_x, neighbor_y, neighbor_z));
+                        }
+                    }
+                }
+            }
+            next_accessible_rolls.sort_unstable_by_key(|(x, y, z)| (*z, *y, *x));
+
+            removed_rolls.push(accessible_rolls);
+            accessible_rolls = next_accessible_rolls;
+        }
+
+        removed_rolls
+    }
+}
+
+pub fn render_layer_waves(
+    removed_rolls: &[Vec<(usize, usize, usize)>],
+    layer_count: usize,
+) -> String {
+    removed_rolls
+        .iter()
+        .enumerate()
+        .map(|(wave, rolls)| {
+            let mut layer_rolls = vec![0; layer_count];
+            for (_, _, z) in rolls {
+                layer_rolls[*z] += 1;
+            }
+
+            let layers = layer_rolls
+                .iter()
+                .enumerate()
+                .map(|(z, count)| format!("layer {}: {count}", z + 1))
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("Wave {}: {} rolls ({layers})\n", wave + 1, rolls.len())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Neighborhood, tests::generate_random_map};
+
+    fn clear_up_map_rescanning(
+        map: &mut LayeredMap,
+        rule: &AccessRule,
+    ) -> Vec<Vec<(usize, usize, usize)>> {
+        let mut removed_rolls = Vec::new();
+
+        while let Some(accessible_rolls) = map.find_accessible_rolls(rule) {
+            for (x, y, z) in &accessible_rolls {
+                map.layers[*z][*y][*x] = false;
+            }
+
+            removed_rolls.push(accessible_rolls);
+        }
+
+        removed_rolls
+    }
+
+    #[test]
+    fn test_parse() {
+        let map = LayeredMap::parse(
+            "
+            @@.
+            .@.
+
+            ..@
+            @..
+        ",
+        );
+
+        assert_eq!(map.layer_count(), 2);
+        assert_eq!(
+            map.layers,
+            vec![
+                vec![vec![true, true, false], vec![false, true, false]],
+                vec![vec![false, false, true], vec![true, false, false]],
+            ]
+        );
+        assert_eq!(get_offsets().count(), 26);
+    }
+
+    #[test]
+    fn test_clear_up_map() {
+        let mut map = LayeredMap::parse(
+            "
+            @@@
+            @@@
+            @@@
+
+            @@@
+            @@@
+            @@@
+        ",
+        );
+
+        assert_eq!(map.count_neighbor_rolls(1, 1, 0, Topology::Open), 17);
+        assert_eq!(map.count_neighbor_rolls(0, 0, 1, Topology::Open), 7);
+        let rule = |threshold| AccessRule {
+            threshold,
+            ..AccessRule::default()
+        };
+        assert_eq!(map.find_accessible_rolls(&rule(7)), None);
+
+        let removed_rolls = map.clear_up_map(&rule(8));
+        assert_eq!(
+            render_layer_waves(&removed_rolls, map.layer_count()),
+            "Wave 1: 8 rolls (layer 1: 4, layer 2: 4)\nWave 2: 8 rolls (layer 1: 4, layer 2: 4)\nWave 3: 2 rolls (layer 1: 1, layer 2: 1)\n"
+        );
+    }
+
+    #[test]
+    fn test_single_layer_matches_moore() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for threshold in [1, 2, 4, 7] {
+            let mut map = generate_random_map(&mut seed, 17, 11);
+            let mut layered_map = LayeredMap {
+                layers: vec![map.clone()],
+            };
+            let rule = AccessRule {
+                neighborhood: Neighborhood::Moore,
+                threshold,
+                ..AccessRule::default()
+            };
+
+            assert_eq!(
+                layered_map.clear_up_map(&rule),
+                crate::clear_up_map(&mut map, &rule)
+                    .iter()
+                    .map(|rolls| rolls.iter().map(|(x, y)| (*x, *y, 0)).collect())
+                    .collect::<Vec<Vec<(usize, usize, usize)>>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_rescanning() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for topology in [Topology::Open, Topology::Torus, Topology::Walls] {
+            for threshold in [1, 4, 9, 13, 20] {
+                let layers = (0..4)
+                    .map(|_| generate_random_map(&mut seed, 9, 7))
+                    .collect::<Vec<Vec<Vec<bool>>>>();
+                let mut map = LayeredMap {
+                    layers: layers.clone(),
+                };
+                let mut rescanned_map = LayeredMap { layers };
+                let rule = AccessRule {
+                    threshold,
+                    topology,
+                    ..AccessRule::default()
+                };
+
+                assert_eq!(
+                    map.clear_up_map(&rule),
+                    clear_up_map_rescanning(&mut rescanned_map, &rule)
+                );
+                assert_eq!(map.layers, rescanned_map.layers);
+            }
+        }
+    }
+}
@@ -0,0 +1,1174 @@
+mod bit_grid;
+mod cell_map;
+mod layered_map;
+mod playback;
+mod ppm;
+mod sparse_map;
+
+use std::{collections::BTreeSet, path::Path, time::Duration};
+
+use bit_grid::BitGrid;
+use cell_map::CellMap;
+use layered_map::LayeredMap;
+use sparse_map::SparseMap;
+
+const MOORE_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+const VON_NEUMANN_OFFSETS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+const MOORE_RADIUS_2_OFFSETS: [(i32, i32); 24] = [
+    (-2, -2),
+    (-1, -2),
+    (0, -2),
+    (1, -2),
+    (2, -2),
+    (-2, -1),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (2, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-2, 1),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (2, 1),
+    (-2, 2),
+    (-1, 2),
+    (0, 2),
+    (1, 2),
+    (2, 2),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Neighborhood {
+    Moore,
+    VonNeumann,
+    MooreRadius2,
+}
+
+impl Neighborhood {
+    fn offsets(&self) -> &'static [(i32, i32)] {
+        match self {
+            Neighborhood::Moore => &MOORE_OFFSETS,
+            Neighborhood::VonNeumann => &VON_NEUMANN_OFFSETS,
+            Neighborhood::MooreRadius2 => &MOORE_RADIUS_2_OFFSETS,
+        }
+    }
+}
+
+// How cells outside the map count: as empty floor, wrapped around to the opposite edge or as
+// occupied walls
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Topology {
+    Open,
+    Torus,
+    Walls,
+}
+
+// A roll is accessible if fewer than `threshold` cells of its neighbourhood hold rolls
+#[derive(Debug, Clone, PartialEq)]
+struct AccessRule {
+    neighborhood: Neighborhood,
+    threshold: usize,
+    topology: Topology,
+}
+
+impl Default for AccessRule {
+    fn default() -> Self {
+        AccessRule {
+            neighborhood: Neighborhood::Moore,
+            threshold: 4,
+            topology: Topology::Open,
+        }
+    }
+}
+
+trait RollMap {
+    fn find_accessible_rolls(&self, rule: &AccessRule) -> Option<Vec<(usize, usize)>>;
+    fn clear_up_map(&mut self, rule: &AccessRule) -> Vec<Vec<(usize, usize)>>;
+}
+
+impl RollMap for Vec<Vec<bool>> {
+    fn find_accessible_rolls(&self, rule: &AccessRule) -> Option<Vec<(usize, usize)>> {
+        find_accessible_rolls(self, rule)
+    }
+
+    fn clear_up_map(&mut self, rule: &AccessRule) -> Vec<Vec<(usize, usize)>> {
+        clear_up_map(self, rule)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RemovalDepth {
+    Empty,
+    Wave(usize),
+    Never,
+}
+
+#[derive(Debug, PartialEq)]
+struct CoreSummary {
+    size: usize,
+    bounding_box: Option<((usize, usize), (usize, usize))>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RemovalPolicy {
+    RowMajor,
+    NearestToOrigin,
+    MostIsolatedFirst,
+}
+
+impl RemovalPolicy {
+    // Accessible rolls are removed in ascending priority, ties are broken in row-major order
+    fn priority(&self, x: usize, y: usize, neighbor_rolls: usize) -> (usize, usize, usize) {
+        match self {
+            RemovalPolicy::RowMajor => (0, y, x),
+            RemovalPolicy::NearestToOrigin => (x + y, y, x),
+            RemovalPolicy::MostIsolatedFirst => (neighbor_rolls, y, x),
+        }
+    }
+}
+
+struct WarehouseOptions {
+    rule: AccessRule,
+    bit_packed: bool,
+    sparse: bool,
+    coordinates_path: Option<String>,
+    layers_path: Option<String>,
+    cells_path: Option<String>,
+    show_depth_map: bool,
+    sequential_policy: Option<RemovalPolicy>,
+    animate: bool,
+    frame_delay: Duration,
+    frames_path: Option<String>,
+    image_path: Option<String>,
+    wave_images_directory: Option<String>,
+    image_scale: usize,
+}
+
+impl WarehouseOptions {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = WarehouseOptions {
+            rule: AccessRule::default(),
+            bit_packed: false,
+            sparse: false,
+            coordinates_path: None,
+            layers_path: None,
+            cells_path: None,
+            show_depth_map: false,
+            sequential_policy: None,
+            animate: false,
+            frame_delay: Duration::from_millis(200),
+            frames_path: None,
+            image_path: None,
+            wave_images_directory: None,
+            image_scale: 1,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--neighborhood" => {
+                    options.rule.neighborhood = match args.next().as_deref() {
+                        Some("moore") => Neighborhood::Moore,
+                        Some("von-neumann") => Neighborhood::VonNeumann,
+                        Some("moore-2") => Neighborhood::MooreRadius2,
+                        neighborhood => panic!("Unknown neighborhood {neighborhood:?}"),
+                    }
+                }
+                "--threshold" => options.rule.threshold = args.next().unwrap().parse().unwrap(),
+                "--topology" => {
+                    options.rule.topology = match args.next().as_deref() {
+                        Some("open") => Topology::Open,
+                        Some("torus") => Topology::Torus,
+                        Some("walls") => Topology::Walls,
+                        topology => panic!("Unknown topology {topology:?}"),
+                    }
+                }
+                "--bit-packed" => options.bit_packed = true,
+                "--sparse" => options.sparse = true,
+                "--coordinates" => options.coordinates_path = args.next(),
+                "--layers" => options.layers_path = args.next(),
+                "--cells" => options.cells_path = args.next(),
+                "--depth-map" => options.show_depth_map = true,
+                "--sequential" => {
+                    options.sequential_policy = match args.next().as_deref() {
+                        Some("row-major") => Some(RemovalPolicy::RowMajor),
+                        Some("nearest") => Some(RemovalPolicy::NearestToOrigin),
+                        Some("most-isolated") => Some(RemovalPolicy::MostIsolatedFirst),
+                        policy => panic!("Unknown removal policy {policy:?}"),
+                    }
+                }
+                "--animate" => options.animate = true,
+                "--frame-delay" => {
+                    options.frame_delay =
+                        Duration::from_millis(args.next().unwrap().parse().unwrap())
+                }
+                "--dump-frames" => options.frames_path = args.next(),
+                "--ppm" => options.image_path = args.next(),
+                "--ppm-waves" => options.wave_images_directory = args.next(),
+                "--ppm-scale" => options.image_scale = args.next().unwrap().parse().unwrap(),
+                _ => panic!("Unknown argument {arg}"),
+            }
+        }
+
+        assert!(options.image_scale > 0, "Image scale has to be positive");
+        if options.sparse || options.coordinates_path.is_some() {
+            assert_eq!(
+                options.rule.topology,
+                Topology::Open,
+                "Sparse maps have no edges, so only the open topology is supported"
+            );
+            assert!(
+                !options.uses_grid(),
+                "Sparse maps only count rolls, so they can't be bit-packed, rendered or cleared sequentially"
+            );
+        }
+
+        if options.cells_path.is_some() {
+            options.assert_cell_map_support();
+        }
+        if options.layers_path.is_some() {
+            assert_eq!(
+                options.rule.neighborhood,
+                Neighborhood::Moore,
+                "Layered maps only support the Moore neighbourhood of all 26 surrounding cells"
+            );
+            assert!(
+                !options.uses_grid(),
+                "Layered maps only count rolls per floor, so they can't be bit-packed, rendered or cleared sequentially"
+            );
+        }
+
+        options
+    }
+
+    // Cell maps replace the puzzle input, but neither the bit grid nor the sequential removal know
+    // about walls and docks
+    fn assert_cell_map_support(&self) {
+        assert!(
+            !self.sparse && self.coordinates_path.is_none() && self.layers_path.is_none(),
+            "Cell maps can't be combined with sparse or layered maps"
+        );
+        assert!(
+            !self.bit_packed && self.sequential_policy.is_none(),
+            "Cell maps can't be bit-packed or cleared sequentially"
+        );
+    }
+
+    // Bit-packing, the sequential removal and every rendering work on a dense grid of rolls
+    fn uses_grid(&self) -> bool {
+        self.bit_packed
+            || self.show_depth_map
+            || self.sequential_policy.is_some()
+            || self.animate
+            || self.frames_path.is_some()
+            || self.image_path.is_some()
+            || self.wave_images_directory.is_some()
+    }
+}
+
+fn parse_input(input: &str) -> Vec<Vec<bool>> {
+    input
+        .trim()
+        .lines()
+        .map(|line| line.trim().chars().map(|c| c == '@').collect())
+        .collect()
+}
+
+fn resolve_cell<T>(x: i32, y: i32, map: &[Vec<T>], topology: Topology) -> Option<(usize, usize)> {
+    let (x, y) = match topology {
+        Topology::Torus if !map.is_empty() => {
+            let y = y.rem_euclid(map.len() as i32);
+            let row_length = map[y as usize].len() as i32;
+            if row_length == 0 {
+                return None;
+            }
+            (x.rem_euclid(row_length), y)
+        }
+        _ => (x, y),
+    };
+
+    if y < 0 || y >= map.len() as i32 || x < 0 || x >= map[y as usize].len() as i32 {
+        None
+    } else {
+        Some((x as usize, y as usize))
+    }
+}
+
+fn check_cell(x: i32, y: i32, map: &[Vec<bool>], topology: Topology) -> Option<bool> {
+    match resolve_cell(x, y, map, topology) {
+        Some((x, y)) => Some(map[y][x]),
+        None if topology == Topology::Walls => Some(true),
+        None => None,
+    }
+}
+
+fn get_neighborhood(
+    x: i32,
+    y: i32,
+    map: &[Vec<bool>],
+    neighborhood: Neighborhood,
+    topology: Topology,
+) -> Vec<Option<bool>> {
+    neighborhood
+        .offsets()
+        .iter()
+        .map(|(dx, dy)| check_cell(x + dx, y + dy, map, topology))
+        .collect()
+}
+
+fn is_accessible(neighborhood: &[Option<bool>], threshold: usize) -> bool {
+    neighborhood.iter().filter(|c| **c == Some(true)).count() < threshold
+}
+
+fn find_accessible_rolls(map: &[Vec<bool>], rule: &AccessRule) -> Option<Vec<(usize, usize)>> {
+    let mut coordinates = Vec::new();
+
+    for y in 0..map.len() {
+        for x in 0..map[y].len() {
+            if map[y][x]
+                && is_accessible(
+                    &get_neighborhood(x as i32, y as i32, map, rule.neighborhood, rule.topology),
+                    rule.threshold,
+                )
+            {
+                coordinates.push((x, y));
+            }
+        }
+    }
+
+    if coordinates.is_empty() {
+        None
+    } else {
+        Some(coordinates)
+    }
+}
+
+fn count_neighbor_rolls(
+    x: i32,
+    y: i32,
+    map: &[Vec<bool>],
+    neighborhood: Neighborhood,
+    topology: Topology,
+) -> usize {
+    neighborhood
+        .offsets()
+        .iter()
+        .filter(|(dx, dy)| check_cell(x + dx, y + dy, map, topology) == Some(true))
+        .count()
+}
+
+fn count_all_neighbor_rolls(map: &[Vec<bool>], rule: &AccessRule) -> Vec<Vec<usize>> {
+    map.iter()
+        .enumerate()
+        .map(|(y, row)| {
+            (0..row.len())
+                .map(|x| {
+                    count_neighbor_rolls(x as i32, y as i32, map, rule.neighborhood, rule.topology)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn clear_up_map(map: &mut [Vec<bool>], rule: &AccessRule) -> Vec<Vec<(usize, usize)>> {
+    let mut neighbor_rolls = count_all_neighbor_rolls(map, rule);
+    let mut is_queued = map
+        .iter()
+        .map(|row| vec![false; row.len()])
+        .collect::<Vec<Vec<bool>>>();
+
+    let mut removed_rolls = Vec::new();
+    let mut accessible_rolls = find_accessible_rolls(map, rule).unwrap_or_default();
+
+    // Only neighbours of removed rolls can become accessible, so the next wave is collected
+    // from them instead of rescanning the whole map
+    while !accessible_rolls.is_empty() {
+        for (x, y) in &accessible_rolls {
+            map[*y][*x] = false;
+        }
+
+        let mut next_accessible_rolls = Vec::new();
+        for (x, y) in &accessible_rolls {
+            for (dx, dy) in rule.neighborhood.offsets() {
+                // Walls are never removed, so only rolls inside the map need updating
+                if let Some((neighbor_x, neighbor_y)) =
+                    resolve_cell(*x as i32 + dx, *y as i32 + dy, map, rule.topology)
+                    && map[neighbor_y][neighbor_x]
+                {
+                    neighbor_rolls[neighbor_y][neighbor_x] -= 1;
+
+                    if neighbor_rolls[neighbor_y][neighbor_x] < rule.threshold
+                        && !is_queued[neighbor_y][neighbor_x]
+                    {
+                        is_queued[neighbor_y][neighbor_x] = true;
+                        next_accessible_rolls.push((neighbor_x, neighbor_y));
+                    }
+                }
+            }
+        }
+        next_accessible_rolls.sort_unstable_by_key(|(x, y)| (*y, *x));
+
+        removed_rolls.push(accessible_rolls);
+        accessible_rolls = next_accessible_rolls;
+    }
+
+    removed_rolls
+}
+
+fn clear_up_map_sequentially(
+    map: &mut [Vec<bool>],
+    rule: &AccessRule,
+    policy: RemovalPolicy,
+) -> Vec<(usize, usize)> {
+    let mut neighbor_rolls = count_all_neighbor_rolls(map, rule);
+    let mut accessible_rolls = find_accessible_rolls(map, rule)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(x, y)| policy.priority(x, y, neighbor_rolls[y][x]))
+        .collect::<BTreeSet<(usize, usize, usize)>>();
+
+    let mut removal_order = Vec::new();
+    while let Some((_, y, x)) = accessible_rolls.pop_first() {
+        map[y][x] = false;
+        removal_order.push((x, y));
+
+        for (dx, dy) in rule.neighborhood.offsets() {
+            if let Some((neighbor_x, neighbor_y)) =
+                resolve_cell(x as i32 + dx, y as i32 + dy, map, rule.topology)
+                && map[neighbor_y][neighbor_x]
+            {
+                let previous_neighbor_rolls = neighbor_rolls[neighbor_y][neighbor_x];
+                neighbor_rolls[neighbor_y][neighbor_x] -= 1;
+
+                // Rolls stay accessible once they are, but their priority may change
+                if previous_neighbor_rolls < rule.threshold {
+                    accessible_rolls.remove(&policy.priority(
+                        neighbor_x,
+                        neighbor_y,
+                        previous_neighbor_rolls,
+                    ));
+                }
+                if previous_neighbor_rolls - 1 < rule.threshold {
+                    accessible_rolls.insert(policy.priority(
+                        neighbor_x,
+                        neighbor_y,
+                        previous_neighbor_rolls - 1,
+                    ));
+                }
+            }
+        }
+    }
+
+    removal_order
+}
+
+fn render_sequential_removal(
+    removal_order: &[(usize, usize)],
+    depths: &[Vec<RemovalDepth>],
+) -> String {
+    let mut latest_wave = 0;
+    let mut out_of_wave_order = 0;
+    let mut rendered = String::new();
+
+    for (step, (x, y)) in removal_order.iter().enumerate() {
+        let wave = match depths[*y][*x] {
+            RemovalDepth::Wave(wave) => wave,
+            _ => unreachable!("Sequential removal cleared a roll the waves keep"),
+        };
+
+        rendered.push_str(&format!("{:>5}: ({x}, {y}) from wave {wave}", step + 1));
+        if wave < latest_wave {
+            out_of_wave_order += 1;
+            rendered.push_str(&format!(" after a roll from wave {latest_wave}"));
+        }
+        rendered.push('\n');
+        latest_wave = latest_wave.max(wave);
+    }
+
+    rendered.push_str(&format!(
+        "{} rolls removed one at a time, {out_of_wave_order} of them out of wave order\n",
+        removal_order.len()
+    ));
+
+    rendered
+}
+
+fn get_removal_depths(
+    map: &[Vec<bool>],
+    removed_rolls: &[Vec<(usize, usize)>],
+) -> Vec<Vec<RemovalDepth>> {
+    let mut depths = map
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|is_roll| {
+                    if *is_roll {
+                        RemovalDepth::Never
+                    } else {
+                        RemovalDepth::Empty
+                    }
+                })
+                .collect::<Vec<RemovalDepth>>()
+        })
+        .collect::<Vec<Vec<RemovalDepth>>>();
+
+    for (wave, rolls) in removed_rolls.iter().enumerate() {
+        for (x, y) in rolls {
+            depths[*y][*x] = RemovalDepth::Wave(wave + 1);
+        }
+    }
+
+    depths
+}
+
+fn render_removal_depths(depths: &[Vec<RemovalDepth>]) -> String {
+    const WAVE_SYMBOLS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    depths
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|depth| match depth {
+                    RemovalDepth::Empty => '.',
+                    RemovalDepth::Wave(wave) => WAVE_SYMBOLS.get(*wave).map_or('+', |c| *c as char),
+                    RemovalDepth::Never => '@',
+                })
+                .chain(['\n'])
+                .collect::<String>()
+        })
+        .collect()
+}
+
+fn summarize_core(depths: &[Vec<RemovalDepth>]) -> CoreSummary {
+    let core_rolls = depths
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, depth)| **depth == RemovalDepth::Never)
+                .map(move |(x, _)| (x, y))
+        })
+        .collect::<Vec<(usize, usize)>>();
+
+    let bounding_box = core_rolls.iter().fold(None, |bounding_box, &(x, y)| {
+        Some(match bounding_box {
+            None => ((x, y), (x, y)),
+            Some(((min_x, min_y), (max_x, max_y))) => {
+                ((x.min(min_x), y.min(min_y)), (x.max(max_x), y.max(max_y)))
+            }
+        })
+    });
+
+    CoreSummary {
+        size: core_rolls.len(),
+        bounding_box,
+    }
+}
+
+fn print_removed_rolls<T>(accessible_rolls: Option<Vec<T>>, removed_rolls: &[Vec<T>]) {
+    // Solution for puzzle 1
+    println!(
+        "{} rolls are immediately accessible via forklift",
+        accessible_rolls.map_or(0, |rolls| rolls.len())
+    );
+
+    // Solution for puzzle 2
+    let overall_removed_rolls = removed_rolls.iter().map(Vec::len).sum::<usize>();
+    println!("{overall_removed_rolls} can be removed overall via forklift");
+}
+
+fn main() {
+    let input = include_str!("../../inputs/data_day_4.txt");
+    let original_map = parse_input(input);
+    let options = WarehouseOptions::from_args(std::env::args().skip(1));
+
+    // Puzzle inputs with walls, fixed rolls or docks need the richer cell map as well
+    let cell_map = match &options.cells_path {
+        Some(cells_path) => Some(CellMap::parse(
+            &std::fs::read_to_string(cells_path).unwrap(),
+        )),
+        None if options.layers_path.is_none()
+            && options.coordinates_path.is_none()
+            && input.contains(['#', 'F', 'D']) =>
+        {
+            options.assert_cell_map_support();
+            Some(CellMap::parse(input))
+        }
+        None => None,
+    };
+
+    if let Some(layers_path) = &options.layers_path {
+        let mut layered_map = LayeredMap::parse(&std::fs::read_to_string(layers_path).unwrap());
+
+        let accessible_rolls = layered_map.find_accessible_rolls(&options.rule);
+        let removed_rolls = layered_map.clear_up_map(&options.rule);
+        print_removed_rolls(accessible_rolls, &removed_rolls);
+        print!(
+            "{}",
+            layered_map::render_layer_waves(&removed_rolls, layered_map.layer_count())
+        );
+
+        return;
+    }
+
+    if options.sparse || options.coordinates_path.is_some() {
+        let mut sparse_map = match &options.coordinates_path {
+            Some(coordinates_path) => {
+                SparseMap::parse_coordinates(&std::fs::read_to_string(coordinates_path).unwrap())
+            }
+            None => SparseMap::from_map(&original_map),
+        };
+
+        let accessible_rolls = sparse_map.find_accessible_rolls(&options.rule);
+        let removed_rolls = sparse_map.clear_up_map(&options.rule);
+        print_removed_rolls(accessible_rolls, &removed_rolls);
+
+        return;
+    }
+
+    let original_map = match &cell_map {
+        Some(cell_map) => cell_map.rolls(),
+        None => original_map,
+    };
+    let mut map: Box<dyn RollMap> = match cell_map {
+        Some(cell_map) => Box::new(cell_map),
+        None if options.bit_packed => Box::new(BitGrid::from_map(&original_map)),
+        None => Box::new(original_map.clone()),
+    };
+
+    let accessible_rolls = map.find_accessible_rolls(&options.rule);
+    let removed_rolls = map.clear_up_map(&options.rule);
+    print_removed_rolls(accessible_rolls, &removed_rolls);
+
+    if options.animate {
+        let frames = playback::render_frames(&original_map, &removed_rolls, true);
+        playback::play_frames(&frames, options.frame_delay);
+    }
+
+    if let Some(frames_path) = &options.frames_path {
+        let frames = playback::render_frames(&original_map, &removed_rolls, false);
+        playback::write_frames(frames_path, &frames).unwrap();
+        println!("Wrote {} frames to {frames_path}", frames.len());
+    }
+
+    let depths = get_removal_depths(&original_map, &removed_rolls);
+
+    if let Some(policy) = options.sequential_policy {
+        let removal_order =
+            clear_up_map_sequentially(&mut original_map.clone(), &options.rule, policy);
+        print!("{}", render_sequential_removal(&removal_order, &depths));
+    }
+
+    if let Some(image_path) = &options.image_path {
+        let image = ppm::render_ppm(&depths, removed_rolls.len(), options.image_scale);
+        std::fs::write(image_path, image).unwrap();
+        println!("Wrote the removal waves to {image_path}");
+    }
+
+    if let Some(wave_images_directory) = &options.wave_images_directory {
+        let image_count = ppm::write_wave_images(
+            Path::new(wave_images_directory),
+            &depths,
+            removed_rolls.len(),
+            options.image_scale,
+        )
+        .unwrap();
+        println!("Wrote {image_count} wave images to {wave_images_directory}");
+    }
+
+    if options.show_depth_map {
+        print!("{}", render_removal_depths(&depths));
+
+        let core_summary = summarize_core(&depths);
+        match core_summary.bounding_box {
+            Some(((min_x, min_y), (max_x, max_y))) => println!(
+                "The stable core has {} rolls within x {min_x}..={max_x} and y {min_y}..={max_y}",
+                core_summary.size
+            ),
+            None => println!("There is no stable core"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_input() {
+        let input = "
+            ..@@
+            @@@.
+        ";
+        assert_eq!(
+            parse_input(input),
+            vec![
+                vec![false, false, true, true],
+                vec![true, true, true, false]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_neighbor() {
+        let map = vec![
+            vec![false, false, true, true],
+            vec![true, true, true, false],
+        ];
+
+        assert_eq!(
+            get_neighborhood(0, 0, &map, Neighborhood::Moore, Topology::Open),
+            [
+                None,
+                None,
+                None,
+                None,
+                Some(false),
+                None,
+                Some(true),
+                Some(true)
+            ]
+        );
+        assert_eq!(
+            get_neighborhood(3, 1, &map, Neighborhood::Moore, Topology::Open),
+            [
+                Some(true),
+                Some(true),
+                None,
+                Some(true),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(
+            get_neighborhood(3, 1, &map, Neighborhood::VonNeumann, Topology::Open),
+            [Some(true), Some(true), None, None]
+        );
+        assert_eq!(
+            get_neighborhood(0, 0, &map, Neighborhood::MooreRadius2, Topology::Open)
+                .iter()
+                .filter(|c| c.is_some())
+                .count(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_is_accessible() {
+        assert!(is_accessible(&[Some(true), None, Some(false)], 2));
+        assert!(!is_accessible(&[Some(true), None, Some(true)], 2));
+    }
+
+    #[test]
+    fn test_find_accessible_rolls() {
+        let map = vec![
+            vec![false, false, false, false],
+            vec![false, false, false, false],
+        ];
+        assert_eq!(find_accessible_rolls(&map, &AccessRule::default()), None);
+
+        let map = vec![vec![false, true, true, true], vec![true, true, true, false]];
+        assert_eq!(
+            find_accessible_rolls(&map, &AccessRule::default()),
+            Some(vec![(3, 0), (0, 1)])
+        );
+        assert_eq!(
+            find_accessible_rolls(
+                &map,
+                &AccessRule {
+                    neighborhood: Neighborhood::VonNeumann,
+                    threshold: 2,
+                    topology: Topology::Open
+                }
+            ),
+            Some(vec![(3, 0), (0, 1)])
+        );
+        assert_eq!(
+            find_accessible_rolls(
+                &map,
+                &AccessRule {
+                    neighborhood: Neighborhood::Moore,
+                    threshold: 5,
+                    topology: Topology::Open
+                }
+            ),
+            Some(vec![(1, 0), (2, 0), (3, 0), (0, 1), (1, 1), (2, 1)])
+        );
+    }
+
+    pub fn generate_access_rules() -> Vec<AccessRule> {
+        [
+            Neighborhood::Moore,
+            Neighborhood::VonNeumann,
+            Neighborhood::MooreRadius2,
+        ]
+        .into_iter()
+        .flat_map(|neighborhood| {
+            [Topology::Open, Topology::Torus, Topology::Walls]
+                .into_iter()
+                .flat_map(move |topology| {
+                    [1, 2, 4, 7, 13].map(|threshold| AccessRule {
+                        neighborhood,
+                        threshold,
+                        topology,
+                    })
+                })
+        })
+        .collect()
+    }
+
+    fn clear_up_map_rescanning(
+        map: &mut [Vec<bool>],
+        rule: &AccessRule,
+    ) -> Vec<Vec<(usize, usize)>> {
+        let mut removed_rolls = Vec::new();
+
+        while let Some(accessible_rolls) = find_accessible_rolls(map, rule) {
+            for (x, y) in &accessible_rolls {
+                map[*y][*x] = false;
+            }
+
+            removed_rolls.push(accessible_rolls);
+        }
+
+        removed_rolls
+    }
+
+    fn next_random(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    pub fn generate_random_map(seed: &mut u64, width: usize, height: usize) -> Vec<Vec<bool>> {
+        (0..height)
+            .map(|_| (0..width).map(|_| next_random(seed) % 10 < 7).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_count_neighbor_rolls() {
+        let map = vec![
+            vec![false, false, true, true],
+            vec![true, true, true, false],
+        ];
+
+        assert_eq!(
+            count_neighbor_rolls(0, 0, &map, Neighborhood::Moore, Topology::Open),
+            2
+        );
+        assert_eq!(
+            count_neighbor_rolls(2, 1, &map, Neighborhood::Moore, Topology::Open),
+            3
+        );
+        assert_eq!(
+            count_neighbor_rolls(2, 1, &map, Neighborhood::VonNeumann, Topology::Open),
+            2
+        );
+        assert_eq!(
+            count_neighbor_rolls(0, 0, &map, Neighborhood::MooreRadius2, Topology::Open),
+            4
+        );
+    }
+
+    #[test]
+    fn test_topologies() {
+        let map = vec![
+            vec![false, false, true, true],
+            vec![true, true, true, false],
+        ];
+
+        assert_eq!(check_cell(-1, 0, &map, Topology::Open), None);
+        assert_eq!(check_cell(-1, 0, &map, Topology::Torus), Some(true));
+        assert_eq!(check_cell(-1, 1, &map, Topology::Torus), Some(false));
+        assert_eq!(check_cell(5, 5, &map, Topology::Walls), Some(true));
+
+        assert_eq!(
+            count_neighbor_rolls(0, 0, &map, Neighborhood::Moore, Topology::Torus),
+            5
+        );
+        assert_eq!(
+            count_neighbor_rolls(0, 0, &map, Neighborhood::Moore, Topology::Walls),
+            7
+        );
+        assert_eq!(
+            find_accessible_rolls(
+                &map,
+                &AccessRule {
+                    topology: Topology::Walls,
+                    ..AccessRule::default()
+                }
+            ),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "only the open topology is supported")]
+    fn test_sparse_options_reject_bounded_topology() {
+        WarehouseOptions::from_args(
+            ["--sparse", "--topology", "walls"]
+                .into_iter()
+                .map(String::from),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Sparse maps only count rolls")]
+    fn test_sparse_options_reject_grid_outputs() {
+        WarehouseOptions::from_args(
+            ["--sparse", "--bit-packed", "--animate"]
+                .into_iter()
+                .map(String::from),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Layered maps only support the Moore neighbourhood")]
+    fn test_layered_options_reject_other_neighborhoods() {
+        WarehouseOptions::from_args(
+            ["--layers", "l.txt", "--neighborhood", "von-neumann"]
+                .into_iter()
+                .map(String::from),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Layered maps only count rolls per floor")]
+    fn test_layered_options_reject_grid_outputs() {
+        WarehouseOptions::from_args(
+            ["--layers", "l.txt", "--ppm", "l.ppm"]
+                .into_iter()
+                .map(String::from),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell maps can't be bit-packed or cleared sequentially")]
+    fn test_cell_options_reject_sequential_removal() {
+        WarehouseOptions::from_args(
+            ["--cells", "c.txt", "--sequential", "row-major"]
+                .into_iter()
+                .map(String::from),
+        );
+    }
+
+    #[test]
+    fn test_clear_up_map() {
+        let mut map = parse_input(
+            "
+            ..@@.@@@@.
+            @@@.@.@.@@
+            @@@@@.@.@@
+            @.@@@@..@.
+            @@.@@@@.@@
+            .@@@@@@@.@
+            .@.@.@.@@@
+            @.@@@.@@@@
+            .@@@@@@@@.
+            @.@.@@@.@.
+        ",
+        );
+        let removed_rolls = clear_up_map(&mut map, &AccessRule::default());
+
+        assert_eq!(removed_rolls[0].len(), 13);
+        assert_eq!(removed_rolls.iter().map(Vec::len).sum::<usize>(), 43);
+        assert!(find_accessible_rolls(&map, &AccessRule::default()).is_none());
+    }
+
+    #[test]
+    fn test_clear_up_map_matches_rescanning() {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+
+        for (width, height) in [(1, 1), (10, 10), (37, 5), (64, 64)] {
+            for rule in generate_access_rules() {
+                let mut map = generate_random_map(&mut seed, width, height);
+                let mut rescanned_map = map.clone();
+
+                assert_eq!(
+                    clear_up_map(&mut map, &rule),
+                    clear_up_map_rescanning(&mut rescanned_map, &rule)
+                );
+                assert_eq!(map, rescanned_map);
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_removal_depths() {
+        let map = parse_input(
+            "
+            @@@@.
+            @@@@.
+            @@@@.
+            @@@@@
+        ",
+        );
+        let mut cleared_map = map.clone();
+        let removed_rolls = clear_up_map(&mut cleared_map, &AccessRule::default());
+
+        assert_eq!(
+            get_removal_depths(&map, &removed_rolls),
+            vec![
+                vec![
+                    RemovalDepth::Wave(1),
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Wave(1),
+                    RemovalDepth::Empty
+                ],
+                vec![
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Empty
+                ],
+                vec![
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Empty
+                ],
+                vec![
+                    RemovalDepth::Wave(1),
+                    RemovalDepth::Never,
+                    RemovalDepth::Never,
+                    RemovalDepth::Wave(2),
+                    RemovalDepth::Wave(1)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_removal_depths() {
+        assert_eq!(
+            render_removal_depths(&[
+                vec![
+                    RemovalDepth::Wave(1),
+                    RemovalDepth::Wave(12),
+                    RemovalDepth::Empty
+                ],
+                vec![
+                    RemovalDepth::Never,
+                    RemovalDepth::Wave(61),
+                    RemovalDepth::Wave(62)
+                ],
+            ]),
+            "1c.\n@Z+\n"
+        );
+    }
+
+    #[test]
+    fn test_summarize_core() {
+        assert_eq!(
+            summarize_core(&[
+                vec![RemovalDepth::Wave(1), RemovalDepth::Never],
+                vec![RemovalDepth::Never, RemovalDepth::Empty],
+                vec![RemovalDepth::Empty, RemovalDepth::Never],
+            ]),
+            CoreSummary {
+                size: 3,
+                bounding_box: Some(((0, 0), (1, 2)))
+            }
+        );
+        assert_eq!(
+            summarize_core(&[vec![RemovalDepth::Wave(1), RemovalDepth::Empty]]),
+            CoreSummary {
+                size: 0,
+                bounding_box: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_clear_up_map_sequentially() {
+        let map = parse_input(
+            "
+            @@@@.
+            @@@@.
+            @@@@.
+            @@@@@
+        ",
+        );
+
+        for (policy, removal_order) in [
+            (
+                RemovalPolicy::RowMajor,
+                vec![(0, 0), (3, 0), (0, 3), (4, 3), (3, 3)],
+            ),
+            (
+                RemovalPolicy::NearestToOrigin,
+                vec![(0, 0), (3, 0), (0, 3), (4, 3), (3, 3)],
+            ),
+            (
+                RemovalPolicy::MostIsolatedFirst,
+                vec![(4, 3), (0, 0), (3, 0), (0, 3), (3, 3)],
+            ),
+        ] {
+            assert_eq!(
+                clear_up_map_sequentially(&mut map.clone(), &AccessRule::default(), policy),
+                removal_order
+            );
+        }
+    }
+
+    #[test]
+    fn test_clear_up_map_sequentially_matches_waves() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for policy in [
+            RemovalPolicy::RowMajor,
+            RemovalPolicy::NearestToOrigin,
+            RemovalPolicy::MostIsolatedFirst,
+        ] {
+            for rule in generate_access_rules() {
+                let mut map = generate_random_map(&mut seed, 12, 9);
+                let mut sequential_map = map.clone();
+
+                let removal_order = clear_up_map_sequentially(&mut sequential_map, &rule, policy);
+                let removed_rolls = clear_up_map(&mut map, &rule).concat();
+
+                assert_eq!(sequential_map, map);
+                assert_eq!(
+                    removal_order.iter().collect::<BTreeSet<_>>(),
+                    removed_rolls.iter().collect::<BTreeSet<_>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_sequential_removal() {
+        let depths = vec![vec![
+            RemovalDepth::Wave(1),
+            RemovalDepth::Wave(2),
+            RemovalDepth::Wave(1),
+        ]];
+
+        assert_eq!(
+            render_sequential_removal(&[(0, 0), (1, 0), (2, 0)], &depths),
+            "    1: (0, 0) from wave 1\n    2: (1, 0) from wave 2\n    3: (2, 0) from wave 1 after a roll from wave 2\n3 rolls removed one at a time, 1 of them out of wave order\n"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    fs,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+fn render_frame(map: &[Vec<bool>], just_removed: &[(usize, usize)], colored: bool) -> String {
+    let mut is_just_removed = map
+        .iter()
+        .map(|row| vec![false; row.len()])
+        .collect::<Vec<Vec<bool>>>();
+    for (x, y) in just_removed {
+        is_just_removed[*y][*x] = true;
+    }
+
+    map.iter()
+        .zip(&is_just_removed)
+        .map(|(row, just_removed_row)| {
+            row.iter()
+                .zip(just_removed_row)
+                .map(
+                    |(is_roll, is_just_removed)| match (is_roll, is_just_removed) {
+                        (_, true) if colored => "\x1b[1;31mx\x1b[0m",
+                        (_, true) => "x",
+                        (true, false) => "@",
+                        (false, false) => ".",
+                    },
+                )
+                .chain(["\n"])
+                .collect::<String>()
+        })
+        .collect()
+}
+
+// The first frame shows the untouched map, every following one the map after a wave with the
+// rolls removed in that wave marked
+pub fn render_frames(
+    map: &[Vec<bool>],
+    removed_rolls: &[Vec<(usize, usize)>],
+    colored: bool,
+) -> Vec<String> {
+    let mut map = map.to_vec();
+    let mut frames = vec![format!("Initial map\n{}", render_frame(&map, &[], colored))];
+
+    for (wave, rolls) in removed_rolls.iter().enumerate() {
+        for (x, y) in rolls {
+            map[*y][*x] = false;
+        }
+        frames.push(format!(
+            "Wave {}: {} rolls removed\n{}",
+            wave + 1,
+            rolls.len(),
+            render_frame(&map, rolls, colored)
+        ));
+    }
+
+    frames
+}
+
+pub fn play_frames(frames: &[String], frame_delay: Duration) {
+    for frame in frames {
+        print!("{CLEAR_SCREEN}{frame}");
+        io::stdout().flush().unwrap();
+        thread::sleep(frame_delay);
+    }
+}
+
+pub fn write_frames(path: &str, frames: &[String]) -> io::Result<()> {
+    fs::write(path, frames.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccessRule, clear_up_map, parse_input};
+
+    #[test]
+    fn test_render_frames() {
+        let mut map = parse_input(
+            "
+            @@@@.
+            @@@@.
+            @@@@.
+            @@@@@
+        ",
+        );
+        let original_map = map.clone();
+        let removed_rolls = clear_up_map(&mut map, &AccessRule::default());
+
+        assert_eq!(
+            render_frames(&original_map, &removed_rolls, false),
+            vec![
+                "Initial map\n@@@@.\n@@@@.\n@@@@.\n@@@@@\n",
+                "Wave 1: 4 rolls removed\nx@@x.\n@@@@.\n@@@@.\nx@@@x\n",
+                "Wave 2: 1 rolls removed\n.@@..\n@@@@.\n@@@@.\n.@@x.\n",
+            ]
+        );
+        assert_eq!(
+            render_frames(&original_map, &removed_rolls, true)[2],
+            "Wave 2: 1 rolls removed\n.@@..\n@@@@.\n@@@@.\n.@@\x1b[1;31mx\x1b[0m.\n"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::{fs, io, path::Path};
+
+use crate::RemovalDepth;
+
+const EMPTY_COLOR: [u8; 3] = [255, 255, 255];
+const CORE_COLOR: [u8; 3] = [40, 40, 40];
+const FIRST_WAVE_COLOR: [u8; 3] = [255, 214, 0];
+const LAST_WAVE_COLOR: [u8; 3] = [200, 0, 0];
+
+// Waves are coloured along a gradient from the first to the last wave
+fn depth_color(depth: RemovalDepth, wave_count: usize) -> [u8; 3] {
+    match depth {
+        RemovalDepth::Empty => EMPTY_COLOR,
+        RemovalDepth::Never => CORE_COLOR,
+        RemovalDepth::Wave(wave) => {
+            let progress = if wave_count > 1 {
+                (wave - 1) as f64 / (wave_count - 1) as f64
+            } else {
+                0.0
+            };
+            [0, 1, 2].map(|channel| {
+                let (first, last) = (
+                    FIRST_WAVE_COLOR[channel] as f64,
+                    LAST_WAVE_COLOR[channel] as f64,
+                );
+                (first + (last - first) * progress).round() as u8
+            })
+        }
+    }
+}
+
+pub fn render_ppm(depths: &[Vec<RemovalDepth>], wave_count: usize, scale: usize) -> Vec<u8> {
+    let width = depths.iter().map(Vec::len).max().unwrap_or(0);
+    let mut image = format!("P6\n{} {}\n255\n", width * scale, depths.len() * scale).into_bytes();
+
+    for row in depths {
+        let scaled_row = (0..width)
+            .flat_map(|x| {
+                let color = depth_color(*row.get(x).unwrap_or(&RemovalDepth::Empty), wave_count);
+                (0..scale).flat_map(move |_| color)
+            })
+            .collect::<Vec<u8>>();
+        for _ in 0..scale {
+            image.extend_from_slice(&scaled_row);
+        }
+    }
+
+    image
+}
+
+// Rolls removed up to and including the given wave are shown as empty floor
+fn get_depths_after_wave(depths: &[Vec<RemovalDepth>], wave: usize) -> Vec<Vec<RemovalDepth>> {
+    depths
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|depth| match depth {
+                    RemovalDepth::Wave(removed_wave) if *removed_wave <= wave => {
+                        RemovalDepth::Empty
+                    }
+                    depth => *depth,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn write_wave_images(
+    directory: &Path,
+    depths: &[Vec<RemovalDepth>],
+    wave_count: usize,
+    scale: usize,
+) -> io::Result<usize> {
+    fs::create_dir_all(directory)?;
+
+    for wave in 0..=wave_count {
+        fs::write(
+            directory.join(format!("wave_{wave:04}.ppm")),
+            render_ppm(&get_depths_after_wave(depths, wave), wave_count, scale),
+        )?;
+    }
+
+    Ok(wave_count + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_color() {
+        assert_eq!(depth_color(RemovalDepth::Empty, 3), EMPTY_COLOR);
+        assert_eq!(depth_color(RemovalDepth::Never, 3), CORE_COLOR);
+        assert_eq!(depth_color(RemovalDepth::Wave(1), 3), FIRST_WAVE_COLOR);
+        assert_eq!(depth_color(RemovalDepth::Wave(2), 3), [228, 107, 0]);
+        assert_eq!(depth_color(RemovalDepth::Wave(3), 3), LAST_WAVE_COLOR);
+        assert_eq!(depth_color(RemovalDepth::Wave(1), 1), FIRST_WAVE_COLOR);
+    }
+
+    #[test]
+    fn test_render_ppm() {
+        let depths = vec![vec![RemovalDepth::Wave(1), RemovalDepth::Never]];
+
+        let mut expected = b"P6\n4 2\n255\n".to_vec();
+        for _ in 0..2 {
+            expected.extend(FIRST_WAVE_COLOR.repeat(2));
+            expected.extend(CORE_COLOR.repeat(2));
+        }
+        assert_eq!(render_ppm(&depths, 1, 2), expected);
+    }
+
+    #[test]
+    fn test_get_depths_after_wave() {
+        let depths = vec![vec![
+            RemovalDepth::Wave(1),
+            RemovalDepth::Wave(2),
+            RemovalDepth::Never,
+            RemovalDepth::Empty,
+        ]];
+
+        assert_eq!(get_depths_after_wave(&depths, 0), depths);
+        assert_eq!(
+            get_depths_after_wave(&depths, 1),
+            vec![vec![
+                RemovalDepth::Empty,
+                RemovalDepth::Wave(2),
+                RemovalDepth::Never,
+                RemovalDepth::Empty
+            ]]
+        );
+    }
+}
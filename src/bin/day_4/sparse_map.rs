@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::AccessRule;
+
+// Only the rolls themselves are stored, so the floor may be arbitrarily large and extend into
+// negative coordinates
+pub struct SparseMap {
+    rolls: HashSet<(i64, i64)>,
+}
+
+impl SparseMap {
+    pub fn from_map(map: &[Vec<bool>]) -> Self {
+        SparseMap {
+            rolls: map
+                .iter()
+                .enumerate()
+                .flat_map(|(y, row)| {
+                    row.iter()
+                        .enumerate()
+                        .filter(|(_, is_roll)| **is_roll)
+                        .map(move |(x, _)| (x as i64, y as i64))
+                })
+                .collect(),
+        }
+    }
+
+    // One `x,y` pair per line
+    pub fn parse_coordinates(input: &str) -> Self {
+        SparseMap {
+            rolls: input
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| line.split_once(',').unwrap())
+                .map(|(x, y)| (x.trim().parse().unwrap(), y.trim().parse().unwrap()))
+                .collect(),
+        }
+    }
+
+    fn neighbors(
+        &self,
+        (x, y): (i64, i64),
+        rule: &AccessRule,
+    ) -> impl Iterator<Item = (i64, i64)> + '_ {
+        rule.neighborhood
+            .offsets()
+            .iter()
+            .map(move |(dx, dy)| (x + *dx as i64, y + *dy as i64))
+    }
+
+    fn count_neighbor_rolls(&self, roll: (i64, i64), rule: &AccessRule) -> usize {
+        self.neighbors(roll, rule)
+            .filter(|neighbor| self.rolls.contains(neighbor))
+            .count()
+    }
+
+    pub fn find_accessible_rolls(&self, rule: &AccessRule) -> Option<Vec<(i64, i64)>> {
+        let mut coordinates = self
+            .rolls
+            .iter()
+            .filter(|roll| self.count_neighbor_rolls(**roll, rule) < rule.threshold)
+            .copied()
+            .collect::<Vec<(i64, i64)>>();
+        coordinates.sort_unstable_by_key(|(x, y)| (*y, *x));
+
+        if coordinates.is_empty() {
+            None
+        } else {
+            Some(coordinates)
+        }
+    }
+
+    pub fn clear_up_map(&mut self, rule: &AccessRule) -> Vec<Vec<(i64, i64)>> {
+        let mut neighbor_rolls = self
+            .rolls
+            .iter()
+            .map(|roll| (*roll, self.count_neighbor_rolls(*roll, rule)))
+            .collect::<HashMap<(i64, i64), usize>>();
+        let mut is_queued = HashSet::new();
+
+        let mut removed_rolls = Vec::new();
+        let mut accessible_rolls = self.find_accessible_rolls(rule).unwrap_or_default();
+
+        while !accessible_rolls.is_empty() {
+            for roll in &accessible_rolls {
+                self.rolls.remove(roll);
+            }
+
+            let mut next_accessible_rolls = Vec::new();
+            for roll in &accessible_rolls {
+                for neighbor in self.neighbors(*roll, rule) {
+                    if let Some(count) = neighbor_rolls.get_mut(&neighbor)
+                        && self.rolls.contains(&neighbor)
+                    {
+                        *count -= 1;
+
+                        if *count < rule.threshold && is_queued.insert(neighbor) {
+                            next_accessible_rolls.push(neighbor);
+                        }
+                    }
+                }
+            }
+            next_accessible_rolls.sort_unstable_by_key(|(x, y)| (*y, *x));
+
+            removed_rolls.push(accessible_rolls);
+            accessible_rolls = next_accessible_rolls;
+        }
+
+        removed_rolls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Topology,
+        tests::{generate_access_rules, generate_random_map},
+    };
+
+    fn to_signed(coordinates: &[(usize, usize)]) -> Vec<(i64, i64)> {
+        coordinates
+            .iter()
+            .map(|(x, y)| (*x as i64, *y as i64))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        let map = SparseMap::parse_coordinates(
+            "
+            0,0
+            -1, 0
+            1000000,-999999
+        ",
+        );
+
+        assert_eq!(
+            map.rolls,
+            HashSet::from([(0, 0), (-1, 0), (1_000_000, -999_999)])
+        );
+    }
+
+    #[test]
+    fn test_clear_up_map() {
+        let mut map = SparseMap::parse_coordinates("-1,-1\n0,-1\n-1,0\n0,0\n5,5");
+
+        assert_eq!(
+            map.find_accessible_rolls(&AccessRule::default()),
+            Some(vec![(-1, -1), (0, -1), (-1, 0), (0, 0), (5, 5)])
+        );
+        assert_eq!(
+            map.clear_up_map(&AccessRule {
+                threshold: 3,
+                ..AccessRule::default()
+            }),
+            vec![vec![(5, 5)]]
+        );
+        assert_eq!(map.rolls.len(), 4);
+    }
+
+    #[test]
+    fn test_matches_dense_map() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for (width, height) in [(1, 1), (4, 2), (13, 9), (40, 25)] {
+            for rule in generate_access_rules()
+                .into_iter()
+                .filter(|rule| rule.topology == Topology::Open)
+            {
+                let mut map = generate_random_map(&mut seed, width, height);
+                let mut sparse_map = SparseMap::from_map(&map);
+
+                assert_eq!(
+                    sparse_map.find_accessible_rolls(&rule),
+                    crate::find_accessible_rolls(&map, &rule).map(|rolls| to_signed(&rolls))
+                );
+                assert_eq!(
+                    sparse_map.clear_up_map(&rule),
+                    crate::clear_up_map(&mut map, &rule)
+                        .iter()
+                        .map(|rolls| to_signed(rolls))
+                        .collect::<Vec<_>>()
+                );
+                assert_eq!(sparse_map.rolls, SparseMap::from_map(&map).rolls);
+            }
+        }
+    }
+}
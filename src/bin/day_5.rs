@@ -1,83 +1,596 @@
-use std::ops::RangeInclusive;
+use std::{
+    cmp::Reverse,
+    fmt::{self, Display},
+    ops::RangeInclusive,
+};
 
-fn parse_input(input: &str) -> (Vec<RangeInclusive<u64>>, Vec<u64>) {
-    let (fresh_ingridients_input, available_ingridients_input) =
-        input.trim().split_once("\n\n").unwrap();
+// Operations applied one after another to the merged fresh ranges, files are read like the
+// puzzle input but only their fresh ranges are used
+#[derive(Debug, PartialEq)]
+enum RangeOperation {
+    Intersect(String),
+    Subtract(String),
+    Complement(u64, u64),
+}
+
+impl Display for RangeOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangeOperation::Intersect(path) => write!(f, "intersecting with {path}"),
+            RangeOperation::Subtract(path) => write!(f, "subtracting {path}"),
+            RangeOperation::Complement(min, max) => write!(f, "complementing within {min}..={max}"),
+        }
+    }
+}
+
+enum GapReportFormat {
+    Text,
+    Csv,
+}
+
+struct IngridientOptions {
+    merge_adjacent: bool,
+    range_operations: Vec<RangeOperation>,
+    explained_ingridients: Vec<u64>,
+    gap_report: Option<GapReportFormat>,
+    largest_gap_count: usize,
+}
+
+impl IngridientOptions {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = IngridientOptions {
+            merge_adjacent: false,
+            range_operations: Vec::new(),
+            explained_ingridients: Vec::new(),
+            gap_report: None,
+            largest_gap_count: 5,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--merge-adjacent" => options.merge_adjacent = true,
+                "--intersect" => options
+                    .range_operations
+                    .push(RangeOperation::Intersect(args.next().unwrap())),
+                "--subtract" => options
+                    .range_operations
+                    .push(RangeOperation::Subtract(args.next().unwrap())),
+                "--complement" => options.range_operations.push(RangeOperation::Complement(
+                    args.next().unwrap().parse().unwrap(),
+                    args.next().unwrap().parse().unwrap(),
+                )),
+                "explain" => options.explained_ingridients.extend(
+                    args.next()
+                        .unwrap()
+                        .split(',')
+                        .map(|ingridient| ingridient.parse::<u64>().unwrap()),
+                ),
+                "--gaps" => {
+                    options.gap_report = match args.next().as_deref() {
+                        Some("text") => Some(GapReportFormat::Text),
+                        Some("csv") => Some(GapReportFormat::Csv),
+                        format => panic!("Unknown gap report format {format:?}"),
+                    }
+                }
+                "--largest-gaps" => {
+                    options.largest_gap_count = args.next().unwrap().parse().unwrap()
+                }
+                _ => panic!("Unknown argument {arg}"),
+            }
+        }
+
+        options
+    }
+}
 
-    let fresh_ingridients = fresh_ingridients_input
+fn parse_ranges(input: &str) -> Vec<RangeInclusive<u64>> {
+    input
         .lines()
         .map(|line| line.trim().split_once("-").unwrap())
         .map(|(start, end)| start.parse::<u64>().unwrap()..=end.parse::<u64>().unwrap())
-        .collect();
-    let available_ingridients = available_ingridients_input
+        .collect()
+}
+
+// Sections after the available ingridients start with a label line, `spoiled:` or `recalled:`,
+// and both list ranges that are never considered fresh
+fn parse_input(input: &str) -> (Vec<RangeInclusive<u64>>, Vec<u64>, Vec<RangeInclusive<u64>>) {
+    let mut sections = input.trim().split("\n\n");
+
+    let fresh_ingridients = parse_ranges(sections.next().unwrap());
+    let available_ingridients = sections
+        .next()
+        .unwrap()
         .lines()
         .map(|line| line.trim().parse::<u64>().unwrap())
         .collect();
 
-    (fresh_ingridients, available_ingridients)
+    let mut spoiled_ingridients = Vec::new();
+    for section in sections {
+        let (label, ranges_input) = section.trim().split_once('\n').unwrap_or((section, ""));
+        match label.trim() {
+            "spoiled:" | "recalled:" => spoiled_ingridients.extend(parse_ranges(ranges_input)),
+            _ => panic!("Unknown section {label}"),
+        }
+    }
+
+    (
+        fresh_ingridients,
+        available_ingridients,
+        spoiled_ingridients,
+    )
+}
+
+// The ranges have to be merged, so they are sorted and disjoint and the only candidate is the
+// first range not ending before the ingridient
+fn is_covered(merged_ranges: &[RangeInclusive<u64>], ingridient: &u64) -> bool {
+    let candidate_index = merged_ranges.partition_point(|range| range.end() < ingridient);
+
+    merged_ranges
+        .get(candidate_index)
+        .is_some_and(|range| range.contains(ingridient))
 }
 
 fn find_fresh_available_ingridients<'a>(
-    fresh_ingridients: &[RangeInclusive<u64>],
+    merged_fresh_ingridients: &[RangeInclusive<u64>],
+    available_ingridients: &'a [u64],
+) -> Vec<&'a u64> {
+    available_ingridients
+        .iter()
+        .filter(|ingridient| is_covered(merged_fresh_ingridients, ingridient))
+        .collect()
+}
+
+fn find_spoiled_available_ingridients<'a>(
+    merged_fresh_ingridients: &[RangeInclusive<u64>],
+    merged_spoiled_ingridients: &[RangeInclusive<u64>],
     available_ingridients: &'a [u64],
 ) -> Vec<&'a u64> {
     available_ingridients
         .iter()
         .filter(|ingridient| {
-            fresh_ingridients
-                .iter()
-                .any(|range| range.contains(ingridient))
+            is_covered(merged_fresh_ingridients, ingridient)
+                && is_covered(merged_spoiled_ingridients, ingridient)
         })
         .collect()
 }
 
+// With `merge_adjacent` ranges without a gap between them, like 3..=5 and 6..=8, are coalesced
+// as well, otherwise only overlapping ones are
 fn merge_ingridient_ranges(
-    ingridient_ranges: Vec<RangeInclusive<u64>>,
+    mut ingridient_ranges: Vec<RangeInclusive<u64>>,
+    merge_adjacent: bool,
 ) -> Vec<RangeInclusive<u64>> {
-    let mut ingridient_ranges = ingridient_ranges.clone();
-    ingridient_ranges.sort_by_key(|range| *range.start());
+    ingridient_ranges.sort_unstable_by_key(|range| *range.start());
 
-    let mut i = 0;
-    while i < (ingridient_ranges.len() - 1) {
-        if ingridient_ranges[i].contains(ingridient_ranges[i + 1].start())
-            || ingridient_ranges[i + 1].contains(ingridient_ranges[i].end())
-        {
-            let next_range = ingridient_ranges.remove(i + 1);
-            ingridient_ranges[i] =
-                *ingridient_ranges[i].start()..=*ingridient_ranges[i].end().max(next_range.end());
+    let mut merged_ranges: Vec<RangeInclusive<u64>> = Vec::with_capacity(ingridient_ranges.len());
+    for range in ingridient_ranges {
+        match merged_ranges.last_mut() {
+            Some(last_range)
+                if *range.start() <= *last_range.end()
+                    || merge_adjacent && *range.start() == *last_range.end() + 1 =>
+            {
+                *last_range = *last_range.start()..=*last_range.end().max(range.end());
+            }
+            _ => merged_ranges.push(range),
+        }
+    }
+
+    merged_ranges
+}
+
+// The original fresh ranges with their line numbers, sorted by start and paired with a segment
+// tree of the largest end below every node, so only subtrees containing a covering range are
+// visited when looking up an ingridient
+struct RangeIndex {
+    ranges: Vec<(usize, RangeInclusive<u64>)>,
+    leaf_count: usize,
+    max_ends: Vec<u64>,
+}
+
+impl RangeIndex {
+    fn new(fresh_ingridients: &[RangeInclusive<u64>]) -> Self {
+        let mut ranges = fresh_ingridients
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(index, range)| (index + 1, range))
+            .collect::<Vec<(usize, RangeInclusive<u64>)>>();
+        ranges.sort_unstable_by_key(|(line, range)| (*range.start(), *line));
+
+        let leaf_count = ranges.len().next_power_of_two();
+        let mut max_ends = vec![0; 2 * leaf_count];
+        for (index, (_, range)) in ranges.iter().enumerate() {
+            max_ends[leaf_count + index] = *range.end();
+        }
+        for node in (1..leaf_count).rev() {
+            max_ends[node] = max_ends[2 * node].max(max_ends[2 * node + 1]);
+        }
+
+        RangeIndex {
+            ranges,
+            leaf_count,
+            max_ends,
+        }
+    }
+
+    fn collect_covering_ranges(
+        &self,
+        node: usize,
+        first_range: usize,
+        candidate_count: usize,
+        ingridient: u64,
+        covering_ranges: &mut Vec<(usize, RangeInclusive<u64>)>,
+    ) {
+        if first_range >= candidate_count || self.max_ends[node] < ingridient {
+            return;
+        }
+
+        if node >= self.leaf_count {
+            covering_ranges.push(self.ranges[first_range].clone());
         } else {
+            let half = (self.leaf_count >> node.ilog2()) / 2;
+            for (child, child_first_range) in
+                [(2 * node, first_range), (2 * node + 1, first_range + half)]
+            {
+                self.collect_covering_ranges(
+                    child,
+                    child_first_range,
+                    candidate_count,
+                    ingridient,
+                    covering_ranges,
+                );
+            }
+        }
+    }
+
+    // Returns the line numbers and ranges covering the ingridient, ordered by line number
+    fn explain(&self, ingridient: u64) -> Vec<(usize, RangeInclusive<u64>)> {
+        // Only ranges starting at or before the ingridient can cover it
+        let candidate_count = self
+            .ranges
+            .partition_point(|(_, range)| *range.start() <= ingridient);
+
+        let mut covering_ranges = Vec::new();
+        self.collect_covering_ranges(1, 0, candidate_count, ingridient, &mut covering_ranges);
+        covering_ranges.sort_unstable_by_key(|(line, _)| *line);
+
+        covering_ranges
+    }
+
+    fn explain_batch(&self, ingridients: &[u64]) -> Vec<Vec<(usize, RangeInclusive<u64>)>> {
+        ingridients
+            .iter()
+            .map(|ingridient| self.explain(*ingridient))
+            .collect()
+    }
+}
+
+// A range may cover all of u64, whose size only fits into u128
+fn count_range_ingridients(range: &RangeInclusive<u64>) -> u128 {
+    (range.end() - range.start()) as u128 + 1
+}
+
+fn count_ingridients(ranges: &[RangeInclusive<u64>]) -> u128 {
+    ranges.iter().map(count_range_ingridients).sum()
+}
+
+// All of the following operations expect merged ranges and return merged ranges again
+fn intersect_ranges(
+    ranges: &[RangeInclusive<u64>],
+    other_ranges: &[RangeInclusive<u64>],
+) -> Vec<RangeInclusive<u64>> {
+    let mut intersection = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < ranges.len() && j < other_ranges.len() {
+        let start = *ranges[i].start().max(other_ranges[j].start());
+        let end = *ranges[i].end().min(other_ranges[j].end());
+        if start <= end {
+            intersection.push(start..=end);
+        }
+
+        if ranges[i].end() < other_ranges[j].end() {
             i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    intersection
+}
+
+fn subtract_ranges(
+    ranges: &[RangeInclusive<u64>],
+    other_ranges: &[RangeInclusive<u64>],
+) -> Vec<RangeInclusive<u64>> {
+    let mut difference = Vec::new();
+    let mut first_overlapping = 0;
+
+    for range in ranges {
+        let (mut start, end) = (*range.start(), *range.end());
+        while other_ranges
+            .get(first_overlapping)
+            .is_some_and(|other_range| *other_range.end() < start)
+        {
+            first_overlapping += 1;
+        }
+
+        let mut is_covered = false;
+        for other_range in other_ranges[first_overlapping..]
+            .iter()
+            .take_while(|other_range| *other_range.start() <= end)
+        {
+            if *other_range.start() > start {
+                difference.push(start..=other_range.start() - 1);
+            }
+            if *other_range.end() >= end {
+                is_covered = true;
+                break;
+            }
+            start = other_range.end() + 1;
+        }
+
+        if !is_covered {
+            difference.push(start..=end);
+        }
+    }
+
+    difference
+}
+
+fn complement_ranges(
+    ranges: &[RangeInclusive<u64>],
+    min: u64,
+    max: u64,
+) -> Vec<RangeInclusive<u64>> {
+    if min > max {
+        return Vec::new();
+    }
+
+    subtract_ranges(&[min..=max], ranges)
+}
+
+#[derive(Debug, PartialEq)]
+struct Gap {
+    range: RangeInclusive<u64>,
+    available_ingridients: Vec<u64>,
+}
+
+// Gaps are the non-fresh ranges between the first and the last fresh ingridient
+fn find_gaps(
+    merged_fresh_ingridients: &[RangeInclusive<u64>],
+    available_ingridients: &[u64],
+) -> Vec<Gap> {
+    let (Some(first_range), Some(last_range)) = (
+        merged_fresh_ingridients.first(),
+        merged_fresh_ingridients.last(),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut available_ingridients = available_ingridients.to_vec();
+    available_ingridients.sort_unstable();
+
+    complement_ranges(
+        merged_fresh_ingridients,
+        *first_range.start(),
+        *last_range.end(),
+    )
+    .into_iter()
+    .map(|range| {
+        let first = available_ingridients.partition_point(|ingridient| ingridient < range.start());
+        let end = available_ingridients.partition_point(|ingridient| ingridient <= range.end());
+        Gap {
+            range,
+            available_ingridients: available_ingridients[first..end].to_vec(),
+        }
+    })
+    .collect()
+}
+
+fn render_gap_report(gaps: &[Gap], largest_gap_count: usize, format: &GapReportFormat) -> String {
+    let render_ingridients = |gap: &Gap, separator: &str| {
+        gap.available_ingridients
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<String>>()
+            .join(separator)
+    };
+
+    match format {
+        GapReportFormat::Text => {
+            let gap_ranges = gaps.iter().map(|gap| gap.range.clone()).collect::<Vec<_>>();
+            let mut report = format!(
+                "{} non-fresh ingridients in {} gaps between the fresh ranges\n",
+                count_ingridients(&gap_ranges),
+                gaps.len()
+            );
+
+            let mut largest_gaps = gaps.iter().collect::<Vec<&Gap>>();
+            largest_gaps.sort_by_key(|gap| {
+                (
+                    Reverse(gap.range.end() - gap.range.start()),
+                    *gap.range.start(),
+                )
+            });
+            report.push_str("Largest gaps:\n");
+            for gap in largest_gaps.iter().take(largest_gap_count) {
+                report.push_str(&format!(
+                    "  {}-{}: {} ingridients\n",
+                    gap.range.start(),
+                    gap.range.end(),
+                    count_range_ingridients(&gap.range)
+                ));
+            }
+
+            report.push_str("Available ingridients per gap:\n");
+            for gap in gaps
+                .iter()
+                .filter(|gap| !gap.available_ingridients.is_empty())
+            {
+                report.push_str(&format!(
+                    "  {}-{}: {}\n",
+                    gap.range.start(),
+                    gap.range.end(),
+                    render_ingridients(gap, ", ")
+                ));
+            }
+
+            report
+        }
+        GapReportFormat::Csv => {
+            let mut csv = String::from("start,end,size,available_ingridients\n");
+            for gap in gaps {
+                csv.push_str(&format!(
+                    "{},{},{},{}\n",
+                    gap.range.start(),
+                    gap.range.end(),
+                    count_range_ingridients(&gap.range),
+                    render_ingridients(gap, " ")
+                ));
+            }
+
+            csv
         }
     }
+}
+
+fn apply_range_operation(
+    ranges: &[RangeInclusive<u64>],
+    range_operation: &RangeOperation,
+    merge_adjacent: bool,
+) -> Vec<RangeInclusive<u64>> {
+    let read_ranges = |path: &str| {
+        let input = std::fs::read_to_string(path).unwrap();
+        let fresh_ingridients_input = input.trim().split("\n\n").next().unwrap();
+        merge_ingridient_ranges(parse_ranges(fresh_ingridients_input), merge_adjacent)
+    };
 
-    ingridient_ranges
+    match range_operation {
+        RangeOperation::Intersect(path) => intersect_ranges(ranges, &read_ranges(path)),
+        RangeOperation::Subtract(path) => subtract_ranges(ranges, &read_ranges(path)),
+        RangeOperation::Complement(min, max) => complement_ranges(ranges, *min, *max),
+    }
 }
 
 fn main() {
     let input = include_str!("../inputs/data_day_5.txt");
-    let (fresh_ingridients, available_ingridients) = parse_input(input);
+    let (fresh_ingridients, available_ingridients, spoiled_ingridients) = parse_input(input);
+    let options = IngridientOptions::from_args(std::env::args().skip(1));
+    let range_index = RangeIndex::new(&fresh_ingridients);
+
+    // Spoiled ingridients are never fresh, whatever the fresh ranges say
+    let merged_spoiled_ingridient_ranges =
+        merge_ingridient_ranges(spoiled_ingridients, options.merge_adjacent);
+    let all_fresh_ingridient_ranges =
+        merge_ingridient_ranges(fresh_ingridients, options.merge_adjacent);
+    let merged_fresh_ingridient_ranges = subtract_ranges(
+        &all_fresh_ingridient_ranges,
+        &merged_spoiled_ingridient_ranges,
+    );
+
+    if !merged_spoiled_ingridient_ranges.is_empty() {
+        let spoiled_available_ingridients = find_spoiled_available_ingridients(
+            &all_fresh_ingridient_ranges,
+            &merged_spoiled_ingridient_ranges,
+            &available_ingridients,
+        );
+        println!(
+            "{} of the available ingridients were reclassified as spoiled",
+            spoiled_available_ingridients.len()
+        );
+    }
 
     // Solution for puzzle 1
     let fresh_available_ingridients =
-        find_fresh_available_ingridients(&fresh_ingridients, &available_ingridients);
+        find_fresh_available_ingridients(&merged_fresh_ingridient_ranges, &available_ingridients);
     println!(
         "{} of the available ingridients are fresh",
         fresh_available_ingridients.len()
     );
 
     // Solution for puzzle 2
-    let merged_fresh_ingridient_ranges = merge_ingridient_ranges(fresh_ingridients);
-    let fresh_ingridients_amount = merged_fresh_ingridient_ranges
-        .iter()
-        .map(|range| range.end() - range.start() + 1)
-        .sum::<u64>();
-    println!("{fresh_ingridients_amount} ingridients are considered to be fresh",);
+    let fresh_ingridients_amount = count_ingridients(&merged_fresh_ingridient_ranges);
+    println!(
+        "{fresh_ingridients_amount} ingridients are considered to be fresh in {} ranges",
+        merged_fresh_ingridient_ranges.len()
+    );
+
+    if let Some(format) = &options.gap_report {
+        let gaps = find_gaps(&merged_fresh_ingridient_ranges, &available_ingridients);
+        print!(
+            "{}",
+            render_gap_report(&gaps, options.largest_gap_count, format)
+        );
+    }
+
+    let mut ranges = merged_fresh_ingridient_ranges;
+    for range_operation in &options.range_operations {
+        ranges = apply_range_operation(&ranges, range_operation, options.merge_adjacent);
+        println!(
+            "{} ingridients in {} ranges after {range_operation}",
+            count_ingridients(&ranges),
+            ranges.len()
+        );
+    }
+
+    let explanations = range_index.explain_batch(&options.explained_ingridients);
+    for (ingridient, covering_ranges) in options.explained_ingridients.iter().zip(explanations) {
+        let spoiled_note = if is_covered(&merged_spoiled_ingridient_ranges, ingridient) {
+            ", but it is spoiled"
+        } else {
+            ""
+        };
+        println!(
+            "Ingridient {ingridient} is covered by {} fresh ranges{spoiled_note}",
+            covering_ranges.len()
+        );
+        for (line, range) in covering_ranges {
+            println!("  line {line}: {}-{}", range.start(), range.end());
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+
     use super::*;
 
+    fn find_fresh_available_ingridients_scanning<'a>(
+        fresh_ingridients: &[RangeInclusive<u64>],
+        available_ingridients: &'a [u64],
+    ) -> Vec<&'a u64> {
+        available_ingridients
+            .iter()
+            .filter(|ingridient| {
+                fresh_ingridients
+                    .iter()
+                    .any(|range| range.contains(ingridient))
+            })
+            .collect()
+    }
+
+    fn next_random(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    fn generate_random_ranges(
+        seed: &mut u64,
+        count: usize,
+        max_id: u64,
+    ) -> Vec<RangeInclusive<u64>> {
+        (0..count)
+            .map(|_| {
+                let start = next_random(seed) % max_id;
+                start..=(start + next_random(seed) % 20).min(max_id)
+            })
+            .collect()
+    }
+
     #[test]
     fn test_parse_input() {
         let input = "
@@ -93,10 +606,57 @@ mod tests {
             17
             32
         ";
-        let (fresh_ingridients, available_ingridients) = parse_input(input);
+        let (fresh_ingridients, available_ingridients, spoiled_ingridients) = parse_input(input);
 
         assert_eq!(fresh_ingridients, vec![3..=5, 10..=14, 16..=20, 12..=18,]);
         assert_eq!(available_ingridients, vec![1, 5, 8, 11, 17, 32]);
+        assert_eq!(spoiled_ingridients, vec![]);
+    }
+
+    #[test]
+    fn test_parse_input_with_spoiled_ingridients() {
+        let input = "
+            3-5
+            10-14
+
+            1
+            5
+
+            spoiled:
+            4-4
+            12-20
+
+            recalled:
+            1-1
+        ";
+        let (fresh_ingridients, available_ingridients, spoiled_ingridients) = parse_input(input);
+
+        assert_eq!(fresh_ingridients, vec![3..=5, 10..=14]);
+        assert_eq!(available_ingridients, vec![1, 5]);
+        assert_eq!(spoiled_ingridients, vec![4..=4, 12..=20, 1..=1]);
+    }
+
+    #[test]
+    fn test_find_spoiled_available_ingridients() {
+        let merged_fresh_ingridients = vec![3..=5, 10..=20];
+        let merged_spoiled_ingridients = vec![1..=3, 11..=16];
+        let available_ingridients = vec![1, 3, 5, 8, 11, 17, 32];
+
+        assert_eq!(
+            find_spoiled_available_ingridients(
+                &merged_fresh_ingridients,
+                &merged_spoiled_ingridients,
+                &available_ingridients
+            ),
+            vec![&3, &11]
+        );
+        assert_eq!(
+            find_fresh_available_ingridients(
+                &subtract_ranges(&merged_fresh_ingridients, &merged_spoiled_ingridients),
+                &available_ingridients
+            ),
+            vec![&5, &17]
+        );
     }
 
     #[test]
@@ -105,18 +665,319 @@ mod tests {
         let available_ingridients = vec![1, 5, 8, 11, 17, 32];
 
         assert_eq!(
-            find_fresh_available_ingridients(&fresh_ingridients, &available_ingridients),
+            find_fresh_available_ingridients(
+                &merge_ingridient_ranges(fresh_ingridients, false),
+                &available_ingridients
+            ),
             vec![&5, &11, &17]
         );
     }
 
+    #[test]
+    fn test_is_covered() {
+        let merged_fresh_ingridients = vec![3..=5, 10..=20];
+
+        assert!(!is_covered(&merged_fresh_ingridients, &2));
+        assert!(is_covered(&merged_fresh_ingridients, &3));
+        assert!(is_covered(&merged_fresh_ingridients, &5));
+        assert!(!is_covered(&merged_fresh_ingridients, &6));
+        assert!(is_covered(&merged_fresh_ingridients, &20));
+        assert!(!is_covered(&merged_fresh_ingridients, &21));
+        assert!(!is_covered(&[], &21));
+    }
+
+    #[test]
+    fn test_find_fresh_available_ingridients_matches_scanning() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for (range_count, max_id) in [(1, 10), (5, 50), (40, 200), (300, 10_000)] {
+            let fresh_ingridients = generate_random_ranges(&mut seed, range_count, max_id);
+            let available_ingridients = (0..500)
+                .map(|_| next_random(&mut seed) % (max_id + 10))
+                .collect::<Vec<u64>>();
+
+            assert_eq!(
+                find_fresh_available_ingridients(
+                    &merge_ingridient_ranges(fresh_ingridients.clone(), false),
+                    &available_ingridients
+                ),
+                find_fresh_available_ingridients_scanning(
+                    &fresh_ingridients,
+                    &available_ingridients
+                )
+            );
+        }
+    }
+
     #[test]
     fn test_merge_ingridient_ranges() {
         let fresh_ingridient_ranges = vec![3..=5, 10..=14, 16..=20, 12..=18];
 
         assert_eq!(
-            merge_ingridient_ranges(fresh_ingridient_ranges),
+            merge_ingridient_ranges(fresh_ingridient_ranges, false),
             vec![3..=5, 10..=20]
         );
+        assert_eq!(merge_ingridient_ranges(vec![], false), vec![]);
+        assert_eq!(
+            merge_ingridient_ranges(vec![6..=8, 3..=5, 12..=12, 9..=10], false),
+            vec![3..=5, 6..=8, 9..=10, 12..=12]
+        );
+        assert_eq!(
+            merge_ingridient_ranges(vec![6..=8, 3..=5, 12..=12, 9..=10], true),
+            vec![3..=10, 12..=12]
+        );
+        assert_eq!(
+            merge_ingridient_ranges(vec![u64::MAX..=u64::MAX, 0..=u64::MAX], true),
+            vec![0..=u64::MAX]
+        );
+    }
+
+    #[test]
+    fn test_range_algebra() {
+        let ranges = vec![3..=5, 10..=20];
+        let other_ranges = vec![0..=3, 5..=11, 14..=15, 20..=30];
+
+        assert_eq!(
+            intersect_ranges(&ranges, &other_ranges),
+            vec![3..=3, 5..=5, 10..=11, 14..=15, 20..=20]
+        );
+        assert_eq!(
+            subtract_ranges(&ranges, &other_ranges),
+            vec![4..=4, 12..=13, 16..=19]
+        );
+        assert_eq!(
+            subtract_ranges(&other_ranges, &ranges),
+            vec![0..=2, 6..=9, 21..=30]
+        );
+        assert_eq!(
+            complement_ranges(&ranges, 0, 25),
+            vec![0..=2, 6..=9, 21..=25]
+        );
+        assert_eq!(complement_ranges(&ranges, 4, 12), vec![6..=9]);
+        assert_eq!(complement_ranges(&[], 7, 7), vec![7..=7]);
+        assert_eq!(complement_ranges(&ranges, 8, 7), vec![]);
+        assert_eq!(count_ingridients(&ranges), 14);
+        assert_eq!(complement_ranges(&[0..=u64::MAX], 0, u64::MAX), vec![]);
+        assert_eq!(
+            count_ingridients(&merge_ingridient_ranges(
+                vec![u64::MAX..=u64::MAX, 0..=u64::MAX],
+                true
+            )),
+            u64::MAX as u128 + 1
+        );
+    }
+
+    #[test]
+    fn test_range_algebra_matches_brute_force() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for (range_count, max_id) in [(0, 10), (1, 10), (5, 30), (40, 200), (200, 2_000)] {
+            let ranges = merge_ingridient_ranges(
+                generate_random_ranges(&mut seed, range_count, max_id),
+                false,
+            );
+            let other_ranges = merge_ingridient_ranges(
+                generate_random_ranges(&mut seed, range_count, max_id),
+                false,
+            );
+            let (min, max) = (max_id / 4, max_id / 2);
+
+            let ids = ranges
+                .iter()
+                .flat_map(|range| range.clone())
+                .collect::<BTreeSet<u64>>();
+            let other_ids = other_ranges
+                .iter()
+                .flat_map(|range| range.clone())
+                .collect::<BTreeSet<u64>>();
+
+            for (result, expected_ids) in [
+                (
+                    intersect_ranges(&ranges, &other_ranges),
+                    ids.intersection(&other_ids)
+                        .copied()
+                        .collect::<BTreeSet<u64>>(),
+                ),
+                (
+                    subtract_ranges(&ranges, &other_ranges),
+                    ids.difference(&other_ids).copied().collect(),
+                ),
+                (
+                    complement_ranges(&ranges, min, max),
+                    (min..=max).filter(|id| !ids.contains(id)).collect(),
+                ),
+            ] {
+                assert_eq!(
+                    result
+                        .iter()
+                        .flat_map(|range| range.clone())
+                        .collect::<BTreeSet<u64>>(),
+                    expected_ids
+                );
+                assert_eq!(count_ingridients(&result), expected_ids.len() as u128);
+                assert!(
+                    result
+                        .windows(2)
+                        .all(|ranges| ranges[0].end() < ranges[1].start())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_ingridient_options_from_args() {
+        let options = IngridientOptions::from_args(
+            [
+                "--merge-adjacent",
+                "--intersect",
+                "other.txt",
+                "--complement",
+                "3",
+                "7",
+                "--subtract",
+                "recalled.txt",
+            ]
+            .into_iter()
+            .map(String::from),
+        );
+
+        assert!(options.merge_adjacent);
+        assert_eq!(options.explained_ingridients, vec![]);
+        assert_eq!(
+            options.range_operations,
+            vec![
+                RangeOperation::Intersect(String::from("other.txt")),
+                RangeOperation::Complement(3, 7),
+                RangeOperation::Subtract(String::from("recalled.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let range_index = RangeIndex::new(&[3..=5, 10..=14, 16..=20, 12..=18]);
+
+        assert_eq!(range_index.explain(5), vec![(1, 3..=5)]);
+        assert_eq!(range_index.explain(8), vec![]);
+        assert_eq!(
+            range_index.explain_batch(&[17, 12, 21]),
+            vec![
+                vec![(3, 16..=20), (4, 12..=18)],
+                vec![(2, 10..=14), (4, 12..=18)],
+                vec![]
+            ]
+        );
+        assert_eq!(RangeIndex::new(&[]).explain(0), vec![]);
+
+        let options = IngridientOptions::from_args(
+            ["explain", "5", "explain", "17,12"]
+                .into_iter()
+                .map(String::from),
+        );
+        assert_eq!(options.explained_ingridients, vec![5, 17, 12]);
+    }
+
+    #[test]
+    fn test_explain_matches_brute_force() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for (range_count, max_id) in [(1, 10), (3, 10), (5, 30), (40, 200), (300, 2_000)] {
+            let fresh_ingridients = generate_random_ranges(&mut seed, range_count, max_id);
+            let range_index = RangeIndex::new(&fresh_ingridients);
+
+            for ingridient in 0..=max_id + 1 {
+                assert_eq!(
+                    range_index.explain(ingridient),
+                    fresh_ingridients
+                        .iter()
+                        .cloned()
+                        .enumerate()
+                        .map(|(index, range)| (index + 1, range))
+                        .filter(|(_, range)| range.contains(&ingridient))
+                        .collect::<Vec<(usize, RangeInclusive<u64>)>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_gaps() {
+        let merged_fresh_ingridients = vec![3..=5, 7..=7, 10..=20, 30..=30];
+        let available_ingridients = vec![32, 1, 8, 25, 6, 11, 9, 6];
+
+        assert_eq!(find_gaps(&[], &available_ingridients), vec![]);
+        assert_eq!(
+            find_gaps(&merged_fresh_ingridients, &available_ingridients),
+            vec![
+                Gap {
+                    range: 6..=6,
+                    available_ingridients: vec![6, 6]
+                },
+                Gap {
+                    range: 8..=9,
+                    available_ingridients: vec![8, 9]
+                },
+                Gap {
+                    range: 21..=29,
+                    available_ingridients: vec![25]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_gap_report() {
+        let gaps = find_gaps(&[3..=5, 7..=7, 10..=20, 30..=30], &[1, 8, 25, 6, 11, 9]);
+
+        assert_eq!(
+            render_gap_report(&gaps, 2, &GapReportFormat::Text),
+            "12 non-fresh ingridients in 3 gaps between the fresh ranges
+Largest gaps:
+  21-29: 9 ingridients
+  8-9: 2 ingridients
+Available ingridients per gap:
+  6-6: 6
+  8-9: 8, 9
+  21-29: 25
+"
+        );
+        assert_eq!(
+            render_gap_report(&gaps, 2, &GapReportFormat::Csv),
+            "start,end,size,available_ingridients
+6,6,1,6
+8,9,2,8 9
+21,29,9,25
+"
+        );
+    }
+
+    #[test]
+    fn test_merge_ingridient_ranges_matches_brute_force() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+
+        for (range_count, max_id) in [(0, 10), (1, 10), (5, 30), (40, 200), (300, 2_000)] {
+            for merge_adjacent in [false, true] {
+                let fresh_ingridients = generate_random_ranges(&mut seed, range_count, max_id);
+                let fresh_ids = fresh_ingridients
+                    .iter()
+                    .flat_map(|range| range.clone())
+                    .collect::<BTreeSet<u64>>();
+
+                let merged_ranges = merge_ingridient_ranges(fresh_ingridients, merge_adjacent);
+
+                assert_eq!(
+                    merged_ranges
+                        .iter()
+                        .map(|range| range.end() - range.start() + 1)
+                        .sum::<u64>(),
+                    fresh_ids.len() as u64
+                );
+                assert!(fresh_ids.iter().all(|id| is_covered(&merged_ranges, id)));
+                assert!(merged_ranges.windows(2).all(|ranges| {
+                    let gap = if merge_adjacent { 1 } else { 0 };
+                    *ranges[0].end() + gap < *ranges[1].start()
+                }));
+            }
+        }
     }
 }